@@ -1,52 +1,142 @@
 extern crate proc_macro;
-use macro_compose::{Collector, Context, Expand, Lint};
+use macro_compose::{
+    lints::{EnsureEnum, FieldsShapeLint},
+    pipeline, Analyze, CaptureKey, Collector, Context, Expand, Lint, OwnedContext, WithCaptured,
+};
 use proc_macro::TokenStream;
 use proc_macro2::Ident;
-use quote::format_ident;
-use syn::{parse_quote, Arm, Data, DeriveInput, Error, Fields, ItemImpl, ItemStruct};
+use quote::{format_ident, quote};
+use syn::{
+    parse_quote, Arm, Data, DeriveInput, Error, ItemImpl, ItemStruct, Lit, Meta, NestedMeta,
+};
 
-#[proc_macro_derive(FromStr)]
+#[proc_macro_derive(FromStr, attributes(from_str))]
 pub fn derive_from_str(item: TokenStream) -> TokenStream {
-    let mut collector = Collector::new();
+    expand_from_str(item.into()).into()
+}
+
+fn expand_from_str(item: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let mut ctx = OwnedContext::new_parse2(Collector::with_name("#[derive(FromStr)]"), item);
+    pipeline! { ctx =>
+        lint EnsureEnum::new(),
+        lint FieldsShapeLint::unit_only(),
+        lint EmptyEnumLint,
+    }
 
-    let mut ctx = Context::new_parse(&mut collector, item);
-    ctx.lint(&EnsureEnumLint);
+    ctx.analyze_into::<VariantNames, _>(&VariantNamesAnalyze);
+    ctx.capture_into::<ErrorStructIdent, _>(&ErrorStructIdentExpand);
+    pipeline! { ctx =>
+        expand WithCaptured::<ErrorStructIdent, _>::new(ErrorStructExpand),
+        expand WithCaptured::<ErrorStructIdent, _>::new(ImplDebugErrorStructExpand),
+        expand WithCaptured::<ErrorStructIdent, _>::new(ImplFromStrExpand),
+    }
+
+    let fallback = ctx.data().map(fallback_impl).unwrap_or_default();
+    ctx.finish_with_fallback(fallback)
+}
 
-    ctx.expand(&ErrorStructExpand);
-    ctx.expand(&ImplDebugErrorStructExpand);
-    ctx.expand(&ImplFromStrExpand);
+/// a `FromStr` impl that is emitted if the expansion failed, so that uses of `from_str` don't cause follow-up errors
+fn fallback_impl(input: &DeriveInput) -> proc_macro2::TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote!(
+        impl #impl_generics ::core::str::FromStr for #ident #ty_generics #where_clause {
+            type Err = ();
 
-    collector.finish()
+            fn from_str(_: &::core::primitive::str) -> ::core::result::Result<Self, Self::Err> {
+                ::core::unimplemented!()
+            }
+        }
+    )
 }
 
-struct EnsureEnumLint;
+struct EmptyEnumLint;
 
-impl Lint<DeriveInput> for EnsureEnumLint {
+impl Lint<DeriveInput> for EmptyEnumLint {
     fn lint(&self, input: &DeriveInput, c: &mut Collector) {
-        match &input.data {
-            Data::Enum(e) => {
-                for variant in e.variants.iter() {
-                    if variant.fields != Fields::Unit {
-                        c.error(Error::new_spanned(&variant.fields, "unexpected fields"))
-                    }
+        if let Data::Enum(e) = &input.data {
+            if e.variants.is_empty() {
+                let message = "enums without variants can't be parsed from any string";
+                c.warning(Error::new_spanned(&input.ident, message));
+            }
+        }
+    }
+}
+
+/// the variants and the strings they're parsed from
+struct VariantNames;
+
+impl CaptureKey for VariantNames {
+    type Value = Vec<(Ident, String)>;
+}
+
+struct VariantNamesAnalyze;
+
+impl Analyze<DeriveInput> for VariantNamesAnalyze {
+    type Output = Vec<(Ident, String)>;
+
+    fn analyze(&self, input: &DeriveInput, c: &mut Collector) -> Option<Self::Output> {
+        let variants = match &input.data {
+            Data::Enum(e) => &e.variants,
+            _ => return None,
+        };
+
+        let mut names = Vec::new();
+        for variant in variants.iter() {
+            let mut name = variant.ident.to_string();
+            for (span, meta) in Context::new_by_ref(c, variant).helper_attrs("from_str") {
+                match rename(&meta) {
+                    Some(rename) => name = rename,
+                    None => c.error_at(span, "expected `#[from_str(rename = \"...\")]`"),
                 }
             }
-            _ => c.error(Error::new_spanned(input, "expected an enum")),
+            names.push((variant.ident.clone(), name));
         }
+        Some(names)
+    }
+}
+
+/// the name set with `#[from_str(rename = "...")]`
+fn rename(meta: &Meta) -> Option<String> {
+    let list = match meta {
+        Meta::List(list) if list.nested.len() == 1 => list,
+        _ => return None,
+    };
+    match &list.nested[0] {
+        NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => match &nv.lit {
+            Lit::Str(name) => Some(name.value()),
+            _ => None,
+        },
+        _ => None,
     }
 }
 
-fn error_struct_ident(input: &DeriveInput) -> Ident {
-    format_ident!("Parse{}Error", &input.ident)
+struct ErrorStructIdent;
+
+impl CaptureKey for ErrorStructIdent {
+    type Value = Ident;
+}
+
+struct ErrorStructIdentExpand;
+
+impl Expand<DeriveInput> for ErrorStructIdentExpand {
+    type Output = Ident;
+
+    fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
+        Some(format_ident!("Parse{}Error", &input.ident))
+    }
 }
 
 struct ErrorStructExpand;
 
-impl Expand<DeriveInput> for ErrorStructExpand {
+impl Expand<(&DeriveInput, &Ident)> for ErrorStructExpand {
     type Output = ItemStruct;
 
-    fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
-        let ident = error_struct_ident(input);
+    fn expand(
+        &self,
+        (input, ident): &(&DeriveInput, &Ident),
+        _: &mut Collector,
+    ) -> Option<Self::Output> {
         let vis = &input.vis;
         Some(parse_quote!(
             #[derive(Clone, PartialEq, Eq)]
@@ -57,16 +147,25 @@ impl Expand<DeriveInput> for ErrorStructExpand {
 
 struct ImplDebugErrorStructExpand;
 
-impl Expand<DeriveInput> for ImplDebugErrorStructExpand {
+impl Expand<(&DeriveInput, &Ident)> for ImplDebugErrorStructExpand {
     type Output = ItemImpl;
 
-    fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
-        let ident = error_struct_ident(input);
+    fn expand(
+        &self,
+        (_, ident): &(&DeriveInput, &Ident),
+        c: &mut Collector,
+    ) -> Option<Self::Output> {
         let ident_name = ident.to_string();
+        let expected = c
+            .captured::<VariantNames>()?
+            .iter()
+            .map(|(_, name)| format!("`{}`", name))
+            .collect::<Vec<_>>()
+            .join(", ");
         Some(parse_quote!(
             impl ::core::fmt::Debug for #ident {
                 fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
-                    write!(f, "unexpected value for {}: {}", #ident_name, &self.0)
+                    write!(f, "unexpected value for {}: {}, expected one of {}", #ident_name, &self.0, #expected)
                 }
             }
         ))
@@ -75,26 +174,26 @@ impl Expand<DeriveInput> for ImplDebugErrorStructExpand {
 
 struct ImplFromStrExpand;
 
-impl Expand<DeriveInput> for ImplFromStrExpand {
+impl Expand<(&DeriveInput, &Ident)> for ImplFromStrExpand {
     type Output = ItemImpl;
 
-    fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
-        let variants = match &input.data {
-            Data::Enum(e) => &e.variants,
-            _ => unreachable!(),
-        };
+    fn expand(
+        &self,
+        (input, error): &(&DeriveInput, &Ident),
+        c: &mut Collector,
+    ) -> Option<Self::Output> {
         let ident = &input.ident;
 
-        let arms = variants.iter().map(|v| -> Arm {
-            let v = &v.ident;
-            let name = v.to_string();
-            parse_quote!(
-                #name => ::core::result::Result::Ok(#ident :: #v)
-            )
-        });
+        let arms = c
+            .captured::<VariantNames>()?
+            .iter()
+            .map(|(v, name)| -> Arm {
+                parse_quote!(
+                    #name => ::core::result::Result::Ok(#ident :: #v)
+                )
+            });
 
         let ident = &input.ident;
-        let error = error_struct_ident(input);
         Some(parse_quote!(
             impl ::core::str::FromStr for #ident {
                 type Err = #error;
@@ -109,3 +208,31 @@ impl Expand<DeriveInput> for ImplFromStrExpand {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::expand_from_str;
+    use quote::quote;
+
+    #[test]
+    fn test_fallback() {
+        let output = expand_from_str(quote!(
+            struct Foo;
+        ))
+        .to_string();
+        assert!(output.contains("expected an enum"));
+        assert!(output.contains("impl :: core :: str :: FromStr for Foo"));
+        assert!(output.contains("unimplemented"));
+    }
+
+    #[test]
+    fn test_no_fallback() {
+        let output = expand_from_str(quote!(
+            enum Foo {
+                Bar,
+            }
+        ))
+        .to_string();
+        assert!(!output.contains("unimplemented"));
+    }
+}
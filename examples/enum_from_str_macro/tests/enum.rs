@@ -5,11 +5,22 @@ use std::str::FromStr;
 pub enum Foo {
     Bar,
     Baz,
+    #[from_str(rename = "qux")]
+    Qux,
 }
 
 #[test]
 fn test_from_str() {
     assert_eq!(Foo::from_str("Bar"), Ok(Foo::Bar));
     assert_eq!(Foo::from_str("Baz"), Ok(Foo::Baz));
-    assert_eq!(Foo::from_str("Qux"), Err(FooError("Qux".to_string())));
+    assert_eq!(Foo::from_str("qux"), Ok(Foo::Qux));
+    assert_eq!(Foo::from_str("Qux"), Err(ParseFooError("Qux".to_string())));
+}
+
+#[test]
+fn test_error_debug() {
+    assert_eq!(
+        format!("{:?}", Foo::from_str("Qux").unwrap_err()),
+        "unexpected value for ParseFooError: Qux, expected one of `Bar`, `Baz`, `qux`"
+    );
 }
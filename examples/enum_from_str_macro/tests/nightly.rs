@@ -0,0 +1,55 @@
+//! checks that the diagnostics are emitted through the `proc_macro::Diagnostic` api when the `nightly-diagnostics` feature is enabled
+#![cfg(feature = "nightly-diagnostics")]
+
+use std::{env, fs, path::Path, process::Command};
+
+const SOURCE: &str = r#"
+#[derive(enum_from_str_macro::FromStr)]
+pub struct Foo;
+
+#[derive(enum_from_str_macro::FromStr)]
+pub enum Bar {}
+"#;
+
+/// check a crate using the derive macro and return the diagnostics printed by cargo
+fn check(source: &str) -> String {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("nightly");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = format!(
+        r#"
+            [package]
+            name = "nightly"
+            version = "0.0.0"
+            edition = "2018"
+
+            [dependencies]
+            enum_from_str_macro = {{ path = {:?}, features = ["nightly-diagnostics"] }}
+
+            [workspace]
+        "#,
+        manifest_dir
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    fs::write(dir.join("src/lib.rs"), source).unwrap();
+    let lockfile = Path::new(manifest_dir).join("Cargo.lock");
+    if lockfile.exists() {
+        fs::copy(lockfile, dir.join("Cargo.lock")).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO"))
+        .arg("check")
+        .arg("--quiet")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn test_diagnostics() {
+    let stderr = check(SOURCE);
+    assert!(stderr.contains("error: #[derive(FromStr)]: expected an enum"));
+    assert!(stderr.contains("warning: enums without variants can't be parsed from any string"));
+    assert!(!stderr.contains("deprecated"));
+}
@@ -0,0 +1,45 @@
+extern crate proc_macro;
+use macro_compose::{
+    lints::{is_result, FnSignatureLint},
+    Collector, Context, Expand,
+};
+use proc_macro::TokenStream;
+use syn::{parse_quote, ItemFn};
+
+#[proc_macro_attribute]
+pub fn log_errors(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut collector = Collector::with_name("#[log_errors]");
+
+    let mut ctx = Context::<ItemFn>::new_parse(&mut collector, item);
+    ctx.lint(
+        &FnSignatureLint::new()
+            .deny_async()
+            .deny_unsafe()
+            .require_output_matches(is_result),
+    );
+
+    ctx.expand(&LogErrorsExpand);
+
+    collector.finish().into()
+}
+
+struct LogErrorsExpand;
+
+impl Expand<ItemFn> for LogErrorsExpand {
+    type Output = ItemFn;
+
+    fn expand(&self, item: &ItemFn, _: &mut Collector) -> Option<Self::Output> {
+        let mut item = item.clone();
+        let name = item.sig.ident.to_string();
+        let output = &item.sig.output;
+        let block = &item.block;
+        item.block = parse_quote!({
+            let result = (move || #output #block)();
+            if let Err(e) = &result {
+                eprintln!("{} failed: {:?}", #name, e);
+            }
+            result
+        });
+        Some(item)
+    }
+}
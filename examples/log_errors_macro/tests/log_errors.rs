@@ -0,0 +1,12 @@
+use log_errors_macro::log_errors;
+
+#[log_errors]
+fn parse(s: &str) -> Result<u8, std::num::ParseIntError> {
+    s.parse()
+}
+
+#[test]
+fn test_log_errors() {
+    assert_eq!(parse("42"), Ok(42));
+    assert!(parse("foo").is_err());
+}
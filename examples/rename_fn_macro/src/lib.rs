@@ -0,0 +1,39 @@
+extern crate proc_macro;
+use macro_compose::{Collector, Context, Expand, Lint};
+use proc_macro::TokenStream;
+use proc_macro2::Ident;
+use syn::ItemFn;
+
+#[proc_macro_attribute]
+pub fn rename(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut collector = Collector::with_name("#[rename]");
+
+    let mut ctx = Context::<(Ident, ItemFn)>::new_parse_attr(&mut collector, attr, item);
+    ctx.lint(&EnsureNewNameLint);
+
+    ctx.expand(&RenameExpand);
+
+    collector.finish().into()
+}
+
+struct EnsureNewNameLint;
+
+impl Lint<(Ident, ItemFn)> for EnsureNewNameLint {
+    fn lint(&self, (name, item): &(Ident, ItemFn), c: &mut Collector) {
+        if *name == item.sig.ident {
+            c.error_spanned(name, "the function already has this name");
+        }
+    }
+}
+
+struct RenameExpand;
+
+impl Expand<(Ident, ItemFn)> for RenameExpand {
+    type Output = ItemFn;
+
+    fn expand(&self, (name, item): &(Ident, ItemFn), _: &mut Collector) -> Option<Self::Output> {
+        let mut item = item.clone();
+        item.sig.ident = name.clone();
+        Some(item)
+    }
+}
@@ -0,0 +1,11 @@
+use rename_fn_macro::rename;
+
+#[rename(bar)]
+fn foo() -> u8 {
+    42
+}
+
+#[test]
+fn test_rename() {
+    assert_eq!(bar(), 42);
+}
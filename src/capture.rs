@@ -0,0 +1,104 @@
+use proc_macro2::{Span, TokenStream};
+use std::{any::type_name, marker::PhantomData};
+
+use crate::{Collector, ErasedExpand, Expand};
+
+/// a type-level key for a value captured with [`Context::capture_into`](crate::Context::capture_into)
+///
+/// # Example
+/// ```
+/// use macro_compose::CaptureKey;
+/// use syn::Ident;
+///
+/// struct ErrorStructIdent;
+///
+/// impl CaptureKey for ErrorStructIdent {
+///     type Value = Ident;
+/// }
+/// ```
+pub trait CaptureKey: 'static {
+    /// the type of the captured value
+    type Value: 'static;
+}
+
+/// run an `Expand` with the input and a value captured earlier with [`Context::capture_into`](crate::Context::capture_into)
+///
+/// the inner `Expand` is run with a tuple of the input and the captured value.
+/// if no value has been captured for the key an error is reported and the inner `Expand` isn't run.
+/// the value stays in the collector while the inner `Expand` runs, so it can still be accessed with [`Collector::captured`]
+///
+/// # Example
+/// ```
+/// use macro_compose::{CaptureKey, Collector, Context, Expand, WithCaptured};
+/// use quote::format_ident;
+/// use syn::{parse_quote, DeriveInput, Ident, ItemStruct};
+///
+/// struct ErrorStructIdent;
+///
+/// impl CaptureKey for ErrorStructIdent {
+///     type Value = Ident;
+/// }
+///
+/// struct ErrorStructIdentExpand;
+///
+/// impl Expand<DeriveInput> for ErrorStructIdentExpand {
+///     type Output = Ident;
+///
+///     fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
+///         Some(format_ident!("Parse{}Error", input.ident))
+///     }
+/// }
+///
+/// struct ErrorStructExpand;
+///
+/// impl Expand<(&DeriveInput, &Ident)> for ErrorStructExpand {
+///     type Output = ItemStruct;
+///
+///     fn expand(&self, (input, ident): &(&DeriveInput, &Ident), _: &mut Collector) -> Option<Self::Output> {
+///         let vis = &input.vis;
+///         Some(parse_quote!(#vis struct #ident;))
+///     }
+/// }
+///
+/// let input: DeriveInput = parse_quote!(pub enum Foo {});
+///
+/// let mut collector = Collector::new();
+/// let mut ctx = Context::new(&mut collector, input);
+/// ctx.capture_into::<ErrorStructIdent, _>(&ErrorStructIdentExpand);
+/// ctx.expand(&WithCaptured::<ErrorStructIdent, _>::new(ErrorStructExpand));
+/// assert_eq!(collector.finish().to_string(), "pub struct ParseFooError ;");
+/// ```
+pub struct WithCaptured<K, E> {
+    expand: E,
+    key: PhantomData<fn() -> K>,
+}
+
+impl<K, E> WithCaptured<K, E> {
+    /// create a new adapter for `expand`
+    pub fn new(expand: E) -> Self {
+        WithCaptured {
+            expand,
+            key: PhantomData,
+        }
+    }
+}
+
+impl<T, K, E> Expand<T> for WithCaptured<K, E>
+where
+    K: CaptureKey,
+    E: for<'a> ErasedExpand<(&'a T, &'a K::Value)>,
+{
+    type Output = TokenStream;
+
+    fn expand(&self, input: &T, c: &mut Collector) -> Option<Self::Output> {
+        let value = match c.shared_captured::<K>() {
+            Some(value) => value,
+            None => {
+                let message = format!("no value has been captured for `{}`", type_name::<K>());
+                c.error_at(Span::call_site(), message);
+                return None;
+            }
+        };
+        self.expand.expand_erased(&(input, &*value), c)
+    }
+}
@@ -0,0 +1,96 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Attribute, Data, DeriveInput, Fields};
+
+use crate::{Collector, Lint};
+
+/// the `#[cfg(...)]` attributes
+pub fn cfg_attrs(attrs: &[Attribute]) -> Vec<&Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("cfg"))
+        .collect()
+}
+
+/// prefix the tokens with the `#[cfg(...)]` attributes
+///
+/// this is useful for generating code for variants or fields that might be configured out, eg. match arms
+///
+/// # Example
+/// ```
+/// use macro_compose::with_cfg;
+/// use quote::quote;
+/// use syn::{parse_quote, Variant};
+///
+/// let variant: Variant = parse_quote!(#[cfg(feature = "bar")] #[doc = "bar"] Bar);
+/// let ident = &variant.ident;
+/// let arm = with_cfg(&variant.attrs, quote!(Self::#ident => "bar",));
+/// assert_eq!(
+///     arm.to_string(),
+///     quote!(#[cfg(feature = "bar")] Self::Bar => "bar",).to_string()
+/// );
+/// ```
+pub fn with_cfg(attrs: &[Attribute], tokens: impl ToTokens) -> TokenStream {
+    let cfgs = cfg_attrs(attrs);
+    quote!(#(#cfgs)* #tokens)
+}
+
+/// run the lint on the input without the variants and fields that have `#[cfg(...)]` attributes
+///
+/// the lint doesn't know which `cfg`s are enabled, so all of them are skipped. use [`with_cfg`] to generate code for them in `Expand`s
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::EnsureUnitVariants, CfgAware, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let input: DeriveInput = parse_quote!(
+///     enum Foo {
+///         Foo,
+///         #[cfg(feature = "bar")]
+///         Bar(u8),
+///     }
+/// );
+///
+/// let mut collector = Collector::new();
+/// assert!(Context::new(&mut collector, input).lint(&CfgAware(EnsureUnitVariants::new())));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CfgAware<L>(pub L);
+
+impl<L: Lint<DeriveInput>> Lint<DeriveInput> for CfgAware<L> {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        let mut input = input.clone();
+        match &mut input.data {
+            Data::Struct(s) => remove_cfg_fields(&mut s.fields),
+            Data::Enum(e) => {
+                e.variants = std::mem::take(&mut e.variants)
+                    .into_pairs()
+                    .filter(|pair| cfg_attrs(&pair.value().attrs).is_empty())
+                    .collect();
+                for variant in e.variants.iter_mut() {
+                    remove_cfg_fields(&mut variant.fields);
+                }
+            }
+            Data::Union(u) => {
+                u.fields.named = std::mem::take(&mut u.fields.named)
+                    .into_pairs()
+                    .filter(|pair| cfg_attrs(&pair.value().attrs).is_empty())
+                    .collect();
+            }
+        }
+        self.0.lint(&input, c);
+    }
+}
+
+fn remove_cfg_fields(fields: &mut Fields) {
+    let fields = match fields {
+        Fields::Named(fields) => &mut fields.named,
+        Fields::Unnamed(fields) => &mut fields.unnamed,
+        Fields::Unit => return,
+    };
+    *fields = std::mem::take(fields)
+        .into_pairs()
+        .filter(|pair| cfg_attrs(&pair.value().attrs).is_empty())
+        .collect();
+}
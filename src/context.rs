@@ -1,169 +1,2286 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
 use quote::ToTokens;
-use std::ops::Deref;
-use syn::{parse, parse::Parse, parse2, Error};
+#[cfg(feature = "timings")]
+use std::{any::type_name, time::Duration, time::Instant};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::{self, Debug, Display},
+    iter::FromIterator,
+    ops::Deref,
+    rc::Rc,
+};
+use syn::{
+    parse,
+    parse::{Parse, Parser},
+    parse2,
+    spanned::Spanned,
+    Attribute, DeriveInput, Error, Field, Meta, Path, Variant,
+};
 
-use crate::{Expand, Lint};
+use crate::{
+    diagnostic::{attach, map_messages, render_error, render_warning, Attachment},
+    scope, Analyze, CaptureKey, ErasedExpand, Expand, ExpandIter, ExpandMut, Lint, LintMut, Pass,
+};
 
 /// Collector collects the results and errors of a macro expansion
 pub struct Collector {
-    err_count: usize,
-    output: TokenStream,
+    /// tokens added with `Collector::prepend`
+    prefix: TokenStream,
+    /// the generated items, some of which might be reserved slots
+    items: Vec<TokenStream>,
+    /// named sections of the output in the order they were first used
+    sections: Vec<(String, TokenStream)>,
+    errors: Vec<Entry>,
+    warnings: Vec<Entry>,
+    dedup_errors: bool,
+    sort_diagnostics: bool,
+    lint_gating: bool,
+    max_errors: Option<usize>,
+    name: String,
+    lint_only: bool,
+    error_mappers: Vec<Box<dyn FnMut(Error) -> Error>>,
+    /// the number of errors that were reported, but discarded
+    suppressed: usize,
+    /// the error count at the last recovery point, see `Collector::recover`
+    recovered: usize,
+    /// the keys of errors reported with `Collector::error_once`
+    once_keys: Vec<String>,
+    output_budget: Option<usize>,
+    #[cfg(feature = "full")]
+    verify_output: bool,
+    /// the number of top-level token trees added to the output
+    output_size: usize,
+    /// the number of items kept by every rollback, used to detect slots removed by a rollback
+    rollbacks: Vec<usize>,
+    /// the values captured with `Context::capture_into`
+    captured: HashMap<TypeId, Rc<dyn Any>>,
+    /// the names of the currently running named passes, see `Context::expand_named`
+    passes: Vec<String>,
+    /// the durations of the executed passes
+    #[cfg(feature = "timings")]
+    timings: Vec<(String, Duration)>,
+}
+
+/// an error reported to a collector
+struct Entry {
+    error: Error,
+    attachments: Vec<Attachment>,
+    code: Option<String>,
+    /// the names of the passes the error was reported in, the innermost pass is last
+    passes: Vec<String>,
+}
+
+impl Entry {
+    /// the error with the code prepended and the names of the passes appended to its messages
+    fn coded_error(&self) -> Error {
+        let error = match &self.code {
+            Some(code) => map_messages(&self.error, |_, message| format!("[{}] {}", code, message)),
+            None => self.error.clone(),
+        };
+        if self.passes.is_empty() {
+            return error;
+        }
+        let passes = self
+            .passes
+            .iter()
+            .rev()
+            .map(|pass| format!("while {}", pass))
+            .collect::<Vec<_>>()
+            .join(", ");
+        map_messages(&error, |_, message| format!("{} ({})", message, passes))
+    }
+}
+
+/// an error reported to a collector together with its code, see [`Collector::entries`]
+#[derive(Clone, Copy, Debug)]
+pub struct ErrorEntry<'a> {
+    /// the code of the error, see [`Collector::error_coded`]
+    pub code: Option<&'a str>,
+    /// the error without the code
+    pub error: &'a Error,
 }
 
 impl Collector {
     /// create a new collector
     pub fn new() -> Self {
         Collector {
-            err_count: 0,
-            output: TokenStream::new(),
+            prefix: TokenStream::new(),
+            items: Vec::new(),
+            sections: Vec::new(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            dedup_errors: false,
+            sort_diagnostics: false,
+            lint_gating: false,
+            max_errors: None,
+            name: String::new(),
+            lint_only: false,
+            error_mappers: Vec::new(),
+            suppressed: 0,
+            recovered: 0,
+            once_keys: Vec::new(),
+            output_budget: None,
+            #[cfg(feature = "full")]
+            verify_output: false,
+            output_size: 0,
+            rollbacks: Vec::new(),
+            captured: HashMap::new(),
+            passes: Vec::new(),
+            #[cfg(feature = "timings")]
+            timings: Vec::new(),
+        }
+    }
+
+    /// create a new collector with a name
+    ///
+    /// see [`Collector::set_name`]
+    pub fn with_name(name: impl Into<String>) -> Self {
+        let mut collector = Self::new();
+        collector.set_name(name);
+        collector
+    }
+
+    /// set the name of the macro
+    ///
+    /// the name is used to prefix the messages of rendered errors as `"{name}: {message}"`, eg. `"#[derive(FromStr)]: expected an enum"`.
+    /// the prefix is added when the errors are rendered, so [`Collector::errors`] and [`Collector::take_errors`] still return the original errors.
+    /// an empty name doesn't add a prefix
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// rewrite errors before they are rendered
+    ///
+    /// the mapper is applied when the collector is finished, so [`Collector::errors`] and [`Collector::take_errors`] still return the original errors.
+    /// mappers run in the order they were added and before the name prefix (see [`Collector::set_name`]) is added
+    pub fn map_errors(&mut self, f: impl FnMut(Error) -> Error + 'static) {
+        self.error_mappers.push(Box::new(f));
+    }
+
+    /// create a new collector that deduplicates errors
+    ///
+    /// see [`Collector::set_dedup_errors`]
+    pub fn new_dedup() -> Self {
+        let mut collector = Self::new();
+        collector.set_dedup_errors(true);
+        collector
+    }
+
+    /// enable or disable the deduplication of errors
+    ///
+    /// if enabled an error is discarded if an error with the same messages at the same spans has already been reported.
+    /// discarded errors are not rendered, but they still count as reported errors, eg. [`Context::lint`] still returns false
+    pub fn set_dedup_errors(&mut self, dedup: bool) {
+        self.dedup_errors = dedup;
+    }
+
+    /// create a new collector that only collects diagnostics
+    ///
+    /// see [`Collector::set_lint_only`]
+    pub fn new_lint_only() -> Self {
+        let mut collector = Self::new();
+        collector.set_lint_only(true);
+        collector
+    }
+
+    /// enable or disable the lint only mode
+    ///
+    /// in lint only mode `Expand`s still run, so errors they report are collected, but their output is discarded.
+    /// [`Context::capture`] still returns the output to the caller. [`Collector::append`] discards the tokens as well,
+    /// so [`Collector::finish`] only returns the diagnostics
+    pub fn set_lint_only(&mut self, lint_only: bool) {
+        self.lint_only = lint_only;
+    }
+
+    /// sort errors and warnings by the position of their spans when the collector is finished
+    ///
+    /// this makes the order of the diagnostics independent of the order in which they were reported.
+    /// diagnostics spanned on the call site or whose position is unknown (eg. outside of a proc-macro) are placed after the others in the order they were reported.
+    /// by default diagnostics are rendered in the order they were reported
+    pub fn set_sort_diagnostics(&mut self, sort: bool) {
+        self.sort_diagnostics = sort;
+    }
+
+    /// skip `Lint`s once an error has been reported, like `Expand`s
+    ///
+    /// this avoids follow-up diagnostics once a lint established that the input can't be handled at all, eg. because it isn't an enum.
+    /// skipped lints count as failed, so [`Context::lint`] returns false for them.
+    /// by default all lints run, even if errors have been reported
+    pub fn set_lint_gating(&mut self, gating: bool) {
+        self.lint_gating = gating;
+    }
+
+    /// checks if `Lint`s are skipped because lint gating is enabled and errors have been reported, see [`Collector::set_lint_gating`]
+    pub(crate) fn skips_lints(&self) -> bool {
+        self.lint_gating && self.has_new_errors()
+    }
+
+    /// limit the number of errors that are rendered
+    ///
+    /// errors reported after the first `max` errors are still counted, but not rendered.
+    /// instead [`Collector::finish`] appends a single error stating how many errors were omitted.
+    /// by default all errors are rendered
+    pub fn set_max_errors(&mut self, max: usize) {
+        self.max_errors = Some(max);
+    }
+
+    /// set the maximum number of token trees the output should contain
+    ///
+    /// if the output grows larger than the budget, a single warning with the final size of the output is reported when the collector is finished.
+    /// only the top-level token trees of the appended tokens are counted, so the tokens inside of a group (eg. the body of an impl block) don't count towards the budget
+    ///
+    /// tokens are only counted while a budget is set, so tokens appended before calling this don't count towards the budget.
+    /// counting walks a copy of the appended tokens, without a budget the output is never walked
+    pub fn set_output_budget(&mut self, tokens: usize) {
+        self.output_budget = Some(tokens);
+    }
+
+    /// enable or disable verifying the output when the collector is finished
+    ///
+    /// if enabled and no errors were reported, the generated items are parsed as a [`syn::File`] when the collector is finished.
+    /// if that fails an error is reported, so that a bug in the macro doesn't surface as confusing errors in the code of its users.
+    /// this requires the `full` feature
+    #[cfg(feature = "full")]
+    pub fn set_verify_output(&mut self, verify: bool) {
+        self.verify_output = verify;
+    }
+
+    /// the durations of the `Lint`s and `Expand`s run so far, identified by their type names
+    ///
+    /// if the `MACRO_COMPOSE_TIMINGS` environment variable is set a summary is printed to stderr when the collector is finished
+    #[cfg(feature = "timings")]
+    pub fn timings(&self) -> &[(String, Duration)] {
+        &self.timings
+    }
+
+    /// report an error
+    ///
+    /// once an error has been reported to an collector, `Expand`s will no longer be run
+    pub fn error(&mut self, e: Error) {
+        self.push_error(Entry {
+            error: e,
+            attachments: Vec::new(),
+            code: None,
+            passes: Vec::new(),
+        });
+    }
+
+    /// report an error with an error code
+    ///
+    /// the code is prepended to the rendered messages of the error as `"[{code}] {message}"`, eg. `"[MYMACRO001] expected an enum"`.
+    /// if the collector has a name (see [`Collector::set_name`]) the name is prepended before the code.
+    /// [`Collector::errors`] returns the error without the code, use [`Collector::entries`] to get the codes
+    pub fn error_coded(&mut self, code: &str, e: Error) {
+        self.push_error(Entry {
+            error: e,
+            attachments: Vec::new(),
+            code: Some(code.to_string()),
+            passes: Vec::new(),
+        });
+    }
+
+    /// report an error spanned on `tokens`
+    ///
+    /// this is a shorthand for `c.error(Error::new_spanned(tokens, message))`
+    pub fn error_spanned(&mut self, tokens: impl ToTokens, message: impl Display) {
+        self.error(Error::new_spanned(tokens, message));
+    }
+
+    /// report an error at `span`
+    ///
+    /// this is a shorthand for `c.error(Error::new(span, message))`
+    pub fn error_at(&mut self, span: Span, message: impl Display) {
+        self.error(Error::new(span, message));
+    }
+
+    /// report an error only once for every key
+    ///
+    /// subsequent errors with a key that has already been used are discarded, but still count towards [`Collector::has_errors`] and [`Collector::error_count`].
+    /// this is useful if a lint that runs on every field detects an issue that should only be reported once
+    pub fn error_once(&mut self, key: impl Into<String>, e: Error) {
+        let key = key.into();
+        if self.once_keys.contains(&key) {
+            self.suppressed += 1;
+            return;
+        }
+        self.once_keys.push(key);
+        self.error(e);
+    }
+
+    /// the value captured for the key, see [`Context::capture_into`]
+    pub fn captured<K: CaptureKey>(&self) -> Option<&K::Value> {
+        self.captured.get(&TypeId::of::<K>()).map(|value| {
+            value
+                .downcast_ref()
+                .expect("the value has the type of the key")
+        })
+    }
+
+    /// store a value for the key, replacing the value captured before
+    ///
+    /// the value can be used by later `Expand`s with [`WithCaptured`](crate::WithCaptured)
+    pub fn set_captured<K: CaptureKey>(&mut self, value: K::Value) {
+        self.captured.insert(TypeId::of::<K>(), Rc::new(value));
+    }
+
+    /// a shared handle to the value captured for the key
+    ///
+    /// the value stays in the collector, so it can be lent to an `Expand` that also has access to the collector
+    pub(crate) fn shared_captured<K: CaptureKey>(&self) -> Option<Rc<K::Value>> {
+        self.captured.get(&TypeId::of::<K>()).map(|value| {
+            value
+                .clone()
+                .downcast()
+                .expect("the value has the type of the key")
+        })
+    }
+
+    /// run `f` with this collector as the innermost active collector of the current thread
+    ///
+    /// errors reported with [`report`](crate::report) inside of `f` are reported to this collector once `f` returns or panics.
+    /// scopes can be nested, errors are always reported to the collector of the innermost scope
+    pub fn enter_scope<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        scope::enter(self, f)
+    }
+
+    /// report the error of a result
+    ///
+    /// returns the value if the result is `Ok` and reports the error otherwise
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::Collector;
+    /// use syn::{parse2, Type};
+    /// use quote::quote;
+    ///
+    /// let mut collector = Collector::new();
+    /// let ty: Option<Type> = collector.ok(parse2(quote!(Vec<u8>)));
+    /// assert!(ty.is_some());
+    ///
+    /// let ty: Option<Type> = collector.ok(parse2(quote!(+)));
+    /// assert!(ty.is_none());
+    /// assert!(collector.has_errors());
+    /// ```
+    pub fn ok<T>(&mut self, result: syn::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error(e);
+                None
+            }
+        }
+    }
+
+    /// report `e` if `option` is `None`
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::Collector;
+    /// use proc_macro2::Span;
+    /// use syn::Error;
+    ///
+    /// let mut collector = Collector::new();
+    /// let value = collector.ok_or(Some(1), Error::new(Span::call_site(), "missing value"));
+    /// assert_eq!(value, Some(1));
+    ///
+    /// let value: Option<u8> = collector.ok_or(None, Error::new(Span::call_site(), "missing value"));
+    /// assert_eq!(value, None);
+    /// assert_eq!(collector.error_count(), 1);
+    /// ```
+    pub fn ok_or<T>(&mut self, option: Option<T>, e: Error) -> Option<T> {
+        if option.is_none() {
+            self.error(e);
+        }
+        option
+    }
+
+    /// report an error with a help message
+    ///
+    /// with the `nightly-diagnostics` feature the help message is emitted as a `help` line of the diagnostic.
+    /// on stable it's appended to the first message of the error as `"\nhelp: {help}"`
+    pub fn error_with_help(&mut self, e: Error, help: impl Display) {
+        self.push_error(Entry {
+            error: e,
+            attachments: vec![Attachment::Help(help.to_string())],
+            code: None,
+            passes: Vec::new(),
+        });
+    }
+
+    /// report an error with a note
+    ///
+    /// with the `nightly-diagnostics` feature the note is emitted as a `note` line of the diagnostic.
+    /// on stable it's appended to the first message of the error as `"\nnote: {note}"`
+    pub fn error_with_note(&mut self, e: Error, note: impl Display) {
+        self.push_error(Entry {
+            error: e,
+            attachments: vec![Attachment::Note(note.to_string())],
+            code: None,
+            passes: Vec::new(),
+        });
+    }
+
+    /// report an error with additional context
+    ///
+    /// every message of the error is replaced with `"{context}: {message}"`, the spans of the messages are preserved
+    pub fn error_with_context(&mut self, e: Error, context: impl Display) {
+        let e = map_messages(&e, |_, message| format!("{}: {}", context, message));
+        self.error(e);
+    }
+
+    fn push_error(&mut self, mut entry: Entry) {
+        entry.passes.splice(0..0, self.passes.iter().cloned());
+        if self.dedup_errors {
+            let key = error_key(&entry.error);
+            if self
+                .errors
+                .iter()
+                .any(|other| error_key(&other.error) == key)
+            {
+                self.suppressed += 1;
+                return;
+            }
+        }
+
+        self.errors.push(entry);
+    }
+
+    /// report a warning
+    ///
+    /// unlike [`Collector::error`] warnings don't stop `Expand`s from running and don't count towards [`Collector::has_errors`]
+    ///
+    /// with the `nightly-diagnostics` feature warnings are emitted as real compiler warnings.
+    /// on stable they are rendered on a best-effort basis as `deprecated` warnings, which are not shown if the code they're placed in allows `deprecated`
+    pub fn warning(&mut self, e: Error) {
+        self.warnings.push(Entry {
+            error: e,
+            attachments: Vec::new(),
+            code: None,
+            passes: Vec::new(),
+        });
+    }
+
+    /// turn all errors reported so far into warnings
+    ///
+    /// unlike [`Collector::take_errors`] followed by [`Collector::warning`] the notes, help messages, codes and pass names of the errors are kept
+    pub(crate) fn demote_errors(&mut self) {
+        self.suppressed = 0;
+        self.recovered = 0;
+        self.once_keys.clear();
+        self.warnings.append(&mut self.errors);
+    }
+
+    /// checks if any errors have been reported yet
+    ///
+    /// this includes errors reported before the last recovery point (see [`Collector::recover`]), so it always reflects whether the expansion failed.
+    /// use [`Collector::has_new_errors`] to check whether `Expand`s still run
+    pub fn has_errors(&self) -> bool {
+        self.error_count() != 0
+    }
+
+    /// checks if any errors have been reported since the last recovery point
+    ///
+    /// `Expand`s and [`Collector::append`] only run as long as this is false. without a call to [`Collector::recover`] this is the same as [`Collector::has_errors`]
+    pub fn has_new_errors(&self) -> bool {
+        self.error_count() > self.recovered
+    }
+
+    /// allow `Expand`s to run again even though errors have been reported
+    ///
+    /// this sets a recovery point: `Expand`s run as long as no new errors are reported after it.
+    /// the errors reported before the recovery point are still rendered by [`Collector::finish`] and still count towards [`Collector::has_errors`] and [`Collector::error_count`].
+    /// this is useful if an error only invalidates some of several independent expansions,
+    /// eg. to still emit a trait impl so users don't get follow-up errors about missing impls
+    pub fn recover(&mut self) {
+        self.recovered = self.error_count();
+    }
+
+    /// checks if any items have been added to the output yet
+    ///
+    /// diagnostics don't count as output
+    pub fn has_output(&self) -> bool {
+        !self.prefix.is_empty()
+            || self.items.iter().any(|items| !items.is_empty())
+            || self.sections.iter().any(|(_, items)| !items.is_empty())
+    }
+
+    /// the errors reported so far in the order they were reported
+    pub fn errors(&self) -> impl ExactSizeIterator<Item = &Error> {
+        self.errors.iter().map(|entry| &entry.error)
+    }
+
+    /// the errors reported so far together with their codes in the order they were reported
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = ErrorEntry<'_>> {
+        self.errors.iter().map(|entry| ErrorEntry {
+            code: entry.code.as_deref(),
+            error: &entry.error,
+        })
+    }
+
+    /// combine all errors reported so far into a single error
+    ///
+    /// the spans of all errors are preserved, see [`Error::combine`]. error codes are prepended and notes and help messages are appended to the messages of the errors.
+    /// returns `None` if no errors were reported
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::Collector;
+    /// use proc_macro2::{Span, TokenStream};
+    /// use quote::quote;
+    ///
+    /// fn to_result(collector: Collector) -> syn::Result<TokenStream> {
+    ///     match collector.combined_error() {
+    ///         Some(e) => Err(e),
+    ///         None => Ok(collector.finish()),
+    ///     }
+    /// }
+    ///
+    /// let mut collector = Collector::new();
+    /// collector.append(quote!(struct Foo;));
+    /// assert!(to_result(collector).is_ok());
+    ///
+    /// let mut collector = Collector::new();
+    /// collector.error_at(Span::call_site(), "first");
+    /// collector.error_at(Span::call_site(), "second");
+    /// let e = to_result(collector).unwrap_err();
+    /// assert_eq!(e.into_iter().count(), 2);
+    /// ```
+    pub fn combined_error(&self) -> Option<Error> {
+        let mut errors = self
+            .errors
+            .iter()
+            .map(|entry| attach(&entry.coded_error(), &entry.attachments));
+        let mut combined = errors.next()?;
+        for e in errors {
+            combined.combine(e);
+        }
+        Some(combined)
+    }
+
+    /// the number of errors reported so far
+    ///
+    /// this includes errors that were reported, but not rendered
+    pub fn error_count(&self) -> usize {
+        self.errors.len() + self.suppressed
+    }
+
+    /// finish the expansion and return the result
+    ///
+    /// the diagnostics are placed before the generated items, see [`Collector::finish_split`]
+    pub fn finish(self) -> TokenStream {
+        let (items, mut diagnostics) = self.finish_split();
+        diagnostics.extend(items);
+        diagnostics
+    }
+
+    /// finish the expansion and return the generated items and the rendered diagnostics separately
+    ///
+    /// errors are rendered as `compile_error!` invocations, followed by the warnings.
+    /// with the `nightly-diagnostics` feature they are emitted through [`proc_macro::Diagnostic`] instead when called inside a proc-macro.
+    ///
+    /// the items of named sections (see [`Collector::append_to`]) are placed before the unnamed output in the order the sections were first used
+    pub fn finish_split(mut self) -> (TokenStream, TokenStream) {
+        let diagnostics = self.render_diagnostics();
+        (self.take_items(&[]), diagnostics)
+    }
+
+    /// finish the expansion and return the result with the named sections in the given order
+    ///
+    /// the diagnostics are placed first, followed by the sections in `order`.
+    /// sections that are not in `order` are placed after them in the order they were first used, followed by the unnamed output
+    pub fn finish_ordered(mut self, order: &[&str]) -> TokenStream {
+        let mut output = self.render_diagnostics();
+        output.extend(self.take_items(order));
+        output
+    }
+
+    fn render_diagnostics(&mut self) -> TokenStream {
+        self.print_timings();
+        self.verify();
+        self.check_output_budget();
+        self.sort();
+        let max_errors = self.max_errors.unwrap_or(usize::MAX);
+        let errors = std::mem::take(&mut self.errors);
+        let mut diagnostics = TokenStream::new();
+        for entry in errors.iter().take(max_errors) {
+            let error = self.decorate(entry.coded_error());
+            diagnostics.extend(render_error(&error, &entry.attachments));
+        }
+        let omitted = errors.len().saturating_sub(max_errors);
+        if omitted != 0 {
+            let s = if omitted == 1 { "" } else { "s" };
+            let message = format!("... and {} more error{}", omitted, s);
+            let error = self.decorate(Error::new(Span::call_site(), message));
+            diagnostics.extend(render_error(&error, &[]));
+        }
+        diagnostics.extend(
+            self.warnings
+                .iter()
+                .map(|entry| render_warning(&entry.coded_error(), &entry.attachments)),
+        );
+        diagnostics
+    }
+
+    /// finish the expansion and return the result, adding `fallback` to the output if errors were reported
+    ///
+    /// this can be used to emit dummy items (eg. a trait impl with `unimplemented!()` bodies) when the expansion failed,
+    /// so that users don't get follow-up errors about missing items. the fallback is not emitted if no errors were reported
+    pub fn finish_with_fallback(mut self, fallback: TokenStream) -> TokenStream {
+        self.verify();
+        let has_errors = self.has_errors();
+        let mut output = self.finish();
+        if has_errors {
+            output.extend(fallback);
+        }
+        output
+    }
+
+    /// finish the expansion and return the result if no errors were reported
+    ///
+    /// unlike [`Collector::finish`] the errors aren't rendered into the output, but returned instead.
+    /// notes and help messages are appended to the messages of the errors.
+    /// this is useful when using the collector outside of a proc-macro, eg. in a code generator
+    pub fn finish_result(mut self) -> Result<TokenStream, Vec<Error>> {
+        self.verify();
+        let has_errors = self.has_errors();
+        let (output, errors) = self.into_parts();
+        if has_errors {
+            Err(errors)
+        } else {
+            Ok(output)
+        }
+    }
+
+    /// finish the expansion and return the output and the errors separately
+    ///
+    /// the output contains the generated items followed by the rendered warnings, but no errors.
+    /// like with [`Collector::finish_result`] notes and help messages are appended to the messages of the errors
+    pub fn into_parts(mut self) -> (TokenStream, Vec<Error>) {
+        self.print_timings();
+        self.verify();
+        self.check_output_budget();
+        self.sort();
+        let errors = std::mem::take(&mut self.errors)
+            .into_iter()
+            .map(|entry| attach(&self.decorate(entry.coded_error()), &entry.attachments))
+            .collect();
+        let mut output = self.take_items(&[]);
+        output.extend(
+            self.warnings
+                .iter()
+                .map(|entry| render_warning(&entry.coded_error(), &entry.attachments)),
+        );
+        (output, errors)
+    }
+
+    /// finish the expansion and return the output, panicking if any errors were reported
+    ///
+    /// the panic message lists all errors with their spans. this is meant to be used in tests of `Lint`s and `Expand`s
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::Collector;
+    /// use quote::quote;
+    ///
+    /// let mut collector = Collector::new();
+    /// collector.append(quote!(struct Foo;));
+    /// assert_eq!(collector.expect_success().to_string(), "struct Foo ;");
+    /// ```
+    pub fn expect_success(self) -> TokenStream {
+        if !self.errors.is_empty() {
+            panic!("expected no errors, but {}", self.describe_errors());
+        }
+        self.finish()
+    }
+
+    /// return the errors reported to the collector, panicking if there aren't exactly `n` errors
+    ///
+    /// the panic message lists all errors with their spans. this is meant to be used in tests of `Lint`s and `Expand`s.
+    /// errors discarded by deduplication aren't counted
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::Collector;
+    /// use proc_macro2::Span;
+    ///
+    /// let mut collector = Collector::new();
+    /// collector.error_at(Span::call_site(), "something went wrong");
+    /// let errors = collector.expect_errors(1);
+    /// assert_eq!(errors[0].to_string(), "something went wrong");
+    /// ```
+    pub fn expect_errors(mut self, n: usize) -> Vec<Error> {
+        if self.errors.len() != n {
+            panic!("expected {} error(s), but {}", n, self.describe_errors());
+        }
+        self.take_errors()
+    }
+
+    fn describe_errors(&self) -> String {
+        let mut description = match self.errors.len() {
+            0 => return "no errors were reported".to_string(),
+            1 => "1 error was reported:".to_string(),
+            n => format!("{} errors were reported:", n),
+        };
+        for error in self.errors.iter().flat_map(|entry| entry.error.clone()) {
+            description.push_str(&format!("\n  {} at {:?}", error, error.span()));
+        }
+        description
+    }
+
+    /// remove all errors reported so far and return them
+    ///
+    /// the output is left intact. afterwards the collector no longer has errors, so [`Context::expand`] and [`Context::capture`] run `Expand`s again.
+    /// the keys used with [`Collector::error_once`] are forgotten as well
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        self.suppressed = 0;
+        self.recovered = 0;
+        self.once_keys.clear();
+        self.errors.drain(..).map(|entry| entry.error).collect()
+    }
+
+    /// append tokens to the output
+    ///
+    /// like [`Context::expand`] this does nothing once an error has been reported, use [`Collector::append_unchecked`] to append the tokens anyway
+    pub fn append(&mut self, tokens: impl ToTokens) {
+        if !self.has_new_errors() {
+            self.append_unchecked(tokens);
+        }
+    }
+
+    /// append tokens to the output even if errors have been reported
+    pub fn append_unchecked(&mut self, tokens: impl ToTokens) {
+        self.push_tokens(tokens.into_token_stream());
+    }
+
+    /// append tokens to a named section of the output
+    ///
+    /// sections can be used to control the order of the output independently of the order in which it was generated, see [`Collector::finish_ordered`].
+    /// like [`Collector::append`] this does nothing once an error has been reported
+    pub fn append_to(&mut self, section: &str, tokens: impl ToTokens) {
+        if !self.has_new_errors() {
+            self.push_to_section(section, tokens.into_token_stream());
+        }
+    }
+
+    /// add tokens to the start of the output
+    ///
+    /// the tokens are placed before everything else, including tokens added with earlier calls to `prepend`.
+    /// like [`Collector::append`] this does nothing once an error has been reported
+    pub fn prepend(&mut self, tokens: impl ToTokens) {
+        if !self.has_new_errors() && !self.lint_only {
+            let mut prefix = tokens.into_token_stream();
+            self.count_output(&prefix);
+            prefix.extend(std::mem::take(&mut self.prefix));
+            self.prefix = prefix;
+        }
+    }
+
+    /// reserve a position in the output that can be filled later with [`Collector::fill`]
+    ///
+    /// this is useful if the tokens for a position are only known after more output has been generated
+    pub fn reserve(&mut self) -> Slot {
+        let slot = Slot {
+            index: self.items.len(),
+            rollbacks: self.rollbacks.len(),
+        };
+        self.items.push(TokenStream::new());
+        slot
+    }
+
+    /// add tokens at a position reserved with [`Collector::reserve`]
+    ///
+    /// a slot can be filled multiple times, the tokens are appended to the ones already in the slot.
+    /// like [`Collector::append`] this does nothing once an error has been reported.
+    /// filling a slot that has been removed by [`Collector::rollback`] does nothing as well
+    ///
+    /// filling a slot reserved on another collector is a logic error and might panic
+    pub fn fill(&mut self, slot: Slot, tokens: impl ToTokens) {
+        if self.has_new_errors() || self.lint_only {
+            return;
+        }
+        let removed = self.rollbacks[slot.rollbacks..]
+            .iter()
+            .any(|&items| slot.index >= items);
+        if !removed {
+            let tokens = tokens.into_token_stream();
+            self.count_output(&tokens);
+            self.items[slot.index].extend(tokens);
+        }
+    }
+
+    /// save the current state of the collector so it can be restored with [`Collector::rollback`]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            prefix: self.prefix.clone(),
+            items: self.items.len(),
+            sections: self.sections.clone(),
+            errors: self.errors.len(),
+            warnings: self.warnings.len(),
+            suppressed: self.suppressed,
+            recovered: self.recovered,
+            once_keys: self.once_keys.len(),
+            output_size: self.output_size,
+        }
+    }
+
+    /// restore the state saved with [`Collector::checkpoint`]
+    ///
+    /// all output, errors and warnings added after the checkpoint was taken are removed.
+    /// tokens added to slots that were reserved before the checkpoint are not removed.
+    /// slots reserved after the checkpoint are removed, filling them later does nothing.
+    /// rolling back to a checkpoint of another collector or to a checkpoint taken before an earlier rollback or [`Collector::take_errors`] is a logic error
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.prefix = checkpoint.prefix;
+        self.items.truncate(checkpoint.items);
+        self.rollbacks.push(checkpoint.items);
+        self.sections = checkpoint.sections;
+        self.errors.truncate(checkpoint.errors);
+        self.warnings.truncate(checkpoint.warnings);
+        self.suppressed = checkpoint.suppressed;
+        self.recovered = checkpoint.recovered;
+        self.once_keys.truncate(checkpoint.once_keys);
+        self.output_size = checkpoint.output_size;
+    }
+
+    /// append the output and errors of another collector
+    ///
+    /// the items, errors and warnings of `other` are added after the ones that have already been reported to this collector
+    pub fn merge(&mut self, other: Collector) {
+        self.push_tokens(other.prefix);
+        for items in other.items {
+            self.push_tokens(items);
+        }
+        for (section, items) in other.sections {
+            self.push_to_section(&section, items);
+        }
+        for entry in other.errors {
+            self.push_error(entry);
+        }
+        self.warnings.extend(other.warnings);
+        self.suppressed += other.suppressed;
+        for key in other.once_keys {
+            if !self.once_keys.contains(&key) {
+                self.once_keys.push(key);
+            }
+        }
+        #[cfg(feature = "timings")]
+        self.timings.extend(other.timings);
+    }
+
+    /// create an empty collector with the same options to run a lint against
+    ///
+    /// the name, the diagnostic and lint options and the captured values are inherited,
+    /// the passes and error mappers aren't because they're applied when the errors are merged back into this collector
+    pub(crate) fn scratch(&self) -> Collector {
+        Collector {
+            dedup_errors: self.dedup_errors,
+            sort_diagnostics: self.sort_diagnostics,
+            lint_gating: self.lint_gating,
+            name: self.name.clone(),
+            lint_only: self.lint_only,
+            captured: self.captured.clone(),
+            ..Collector::new()
+        }
+    }
+
+    /// apply the options that change the messages of errors
+    fn decorate(&mut self, mut e: Error) -> Error {
+        for mapper in self.error_mappers.iter_mut() {
+            e = mapper(e);
+        }
+        if self.name.is_empty() {
+            return e;
+        }
+        map_messages(&e, |_, message| format!("{}: {}", self.name, message))
+    }
+
+    pub(crate) fn push_tokens(&mut self, tokens: TokenStream) {
+        if !self.lint_only {
+            self.count_output(&tokens);
+            self.items.push(tokens);
+        }
+    }
+
+    fn push_to_section(&mut self, section: &str, tokens: TokenStream) {
+        if self.lint_only {
+            return;
+        }
+        self.count_output(&tokens);
+        if let Some((_, items)) = self.sections.iter_mut().find(|(name, _)| name == section) {
+            items.extend(tokens);
+        } else {
+            self.sections.push((section.to_string(), tokens));
+        }
+    }
+
+    fn count_output(&mut self, tokens: &TokenStream) {
+        if self.output_budget.is_some() {
+            self.output_size += tokens.clone().into_iter().count();
+        }
+    }
+
+    #[cfg(feature = "timings")]
+    fn print_timings(&self) {
+        if std::env::var_os("MACRO_COMPOSE_TIMINGS").is_none() {
+            return;
+        }
+        eprintln!("macro-compose timings:");
+        for (name, duration) in self.timings.iter() {
+            eprintln!("  {}: {:?}", name, duration);
+        }
+    }
+
+    #[cfg(not(feature = "timings"))]
+    fn print_timings(&self) {}
+
+    #[cfg(feature = "full")]
+    fn verify(&mut self) {
+        if !self.verify_output || self.has_errors() {
+            return;
+        }
+        let mut output = self.prefix.clone();
+        output.extend(self.sections.iter().map(|(_, items)| items.clone()));
+        output.extend(self.items.iter().cloned());
+        if let Err(e) = parse2::<syn::File>(output) {
+            let message = format!(
+                "the macro generated invalid code, this is a bug in the macro: {}",
+                e
+            );
+            self.error(Error::new(Span::call_site(), message));
+        }
+    }
+
+    #[cfg(not(feature = "full"))]
+    fn verify(&mut self) {}
+
+    fn check_output_budget(&mut self) {
+        match self.output_budget {
+            Some(budget) if self.output_size > budget => {
+                let message = format!(
+                    "the expansion generated {} token trees, exceeding the output budget of {}",
+                    self.output_size, budget
+                );
+                self.warning(Error::new(Span::call_site(), message));
+            }
+            _ => {}
+        }
+    }
+
+    fn sort(&mut self) {
+        if self.sort_diagnostics {
+            self.errors
+                .sort_by_cached_key(|entry| span_position(entry.error.span()));
+            self.warnings
+                .sort_by_cached_key(|entry| span_position(entry.error.span()));
+        }
+    }
+
+    fn take_items(&mut self, order: &[&str]) -> TokenStream {
+        let mut sections = std::mem::take(&mut self.sections);
+        let mut output = std::mem::take(&mut self.prefix);
+        for section in order {
+            if let Some(i) = sections.iter().position(|(name, _)| name == section) {
+                output.extend(sections.remove(i).1);
+            }
+        }
+        output.extend(sections.into_iter().map(|(_, items)| items));
+        output.extend(self.items.drain(..));
+        output
+    }
+}
+
+/// the state of a collector saved with [`Collector::checkpoint`]
+pub struct Checkpoint {
+    prefix: TokenStream,
+    items: usize,
+    sections: Vec<(String, TokenStream)>,
+    errors: usize,
+    warnings: usize,
+    suppressed: usize,
+    recovered: usize,
+    once_keys: usize,
+    output_size: usize,
+}
+
+/// a position in the output of a collector reserved with [`Collector::reserve`]
+#[derive(Clone, Copy, Debug)]
+pub struct Slot {
+    index: usize,
+    rollbacks: usize,
+}
+
+/// set the spans of all tokens to `span`
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut tt| {
+            if let TokenTree::Group(group) = &tt {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                tt = TokenTree::Group(respanned);
+            } else {
+                tt.set_span(span);
+            }
+            tt
+        })
+        .collect()
+}
+
+/// the messages and spans of an error used to compare errors
+fn error_key(e: &Error) -> Vec<(String, String)> {
+    e.clone()
+        .into_iter()
+        .map(|e| (e.to_string(), format!("{:?}", e.span())))
+        .collect()
+}
+
+/// the byte position of a span used to sort diagnostics, call site spans and spans with an unknown position are sorted last
+///
+/// the position isn't exposed on stable, but it's part of the `Debug` output (eg. `#0 bytes(10..15)`)
+fn span_position(span: Span) -> (bool, usize) {
+    let debug = format!("{:?}", span);
+    if debug == format!("{:?}", Span::call_site()) {
+        return (true, 0);
+    }
+    let position = debug
+        .find("bytes(")
+        .map(|i| &debug[i + "bytes(".len()..])
+        .and_then(|bytes| bytes.split("..").next())
+        .and_then(|start| start.parse().ok());
+    match position {
+        Some(position) => (false, position),
+        None => (true, 0),
+    }
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// the maximum number of characters of the output shown by the `Debug` implementation of [`Collector`]
+const DEBUG_OUTPUT_LEN: usize = 256;
+
+/// shows the reported errors with their spans and the output generated so far
+///
+/// the output is truncated after a few hundred characters
+impl Debug for Collector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Messages<'a>(&'a [Error]);
+
+        impl Debug for Messages<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list()
+                    .entries(
+                        self.0
+                            .iter()
+                            .flat_map(|e| e.clone())
+                            .map(|e| (e.to_string(), e.span())),
+                    )
+                    .finish()
+            }
+        }
+
+        let mut output = self.prefix.clone();
+        output.extend(self.sections.iter().map(|(_, items)| items.clone()));
+        output.extend(self.items.iter().cloned());
+        let mut output = output.to_string();
+        if let Some((i, _)) = output.char_indices().nth(DEBUG_OUTPUT_LEN) {
+            output.truncate(i);
+            output.push_str("...");
+        }
+
+        let errors: Vec<_> = self
+            .errors
+            .iter()
+            .map(|entry| entry.error.clone())
+            .collect();
+        let warnings: Vec<_> = self
+            .warnings
+            .iter()
+            .map(|entry| entry.error.clone())
+            .collect();
+        f.debug_struct("Collector")
+            .field("error_count", &self.error_count())
+            .field("errors", &Messages(&errors))
+            .field("warnings", &Messages(&warnings))
+            .field("output", &output)
+            .finish()
+    }
+}
+
+/// report every error with [`Collector::error`]
+///
+/// # Example
+/// ```
+/// use macro_compose::Collector;
+/// use proc_macro2::Span;
+/// use syn::Error;
+///
+/// let errors = vec![
+///     Error::new(Span::call_site(), "first"),
+///     Error::new(Span::call_site(), "second"),
+/// ];
+///
+/// let mut collector = Collector::new();
+/// collector.extend(errors.clone());
+/// assert_eq!(collector.error_count(), 2);
+///
+/// let collector: Collector = errors.into_iter().collect();
+/// assert_eq!(collector.error_count(), 2);
+/// ```
+impl Extend<Error> for Collector {
+    fn extend<I: IntoIterator<Item = Error>>(&mut self, iter: I) {
+        for e in iter {
+            self.error(e);
+        }
+    }
+}
+
+/// append every token stream with [`Collector::append`]
+///
+/// like [`Collector::append`] this doesn't add any tokens once an error has been reported
+///
+/// # Example
+/// ```
+/// use macro_compose::Collector;
+/// use quote::quote;
+///
+/// let items = vec![quote!(struct Foo;), quote!(struct Bar;)];
+///
+/// let mut collector = Collector::new();
+/// collector.extend(items);
+/// assert_eq!(collector.finish().to_string(), "struct Foo ; struct Bar ;");
+/// ```
+impl Extend<TokenStream> for Collector {
+    fn extend<I: IntoIterator<Item = TokenStream>>(&mut self, iter: I) {
+        for tokens in iter {
+            self.append(tokens);
+        }
+    }
+}
+
+/// create a collector that contains only the errors
+impl FromIterator<Error> for Collector {
+    fn from_iter<I: IntoIterator<Item = Error>>(iter: I) -> Self {
+        let mut collector = Collector::new();
+        collector.extend(iter);
+        collector
+    }
+}
+
+enum Data<'a, T> {
+    Owned(T),
+    Borrowed(&'a T),
+}
+
+impl<T> Deref for Data<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Data::Owned(data) => data,
+            Data::Borrowed(data) => data,
+        }
+    }
+}
+
+/// used to lint, expand and capture
+///
+/// a context might not have data in it (eg. if parsing the data failed), so calling the lint and expand functions does not guarantee the `Lint`s and `Expand`s actually run.
+/// for a context without data (see [`Context::has_data`]) [`Context::lint`] returns false, [`Context::lint_counted`] returns `None` and `Expand`s don't run.
+/// a context that has no data because parsing it failed is poisoned (see [`Context::is_poisoned`]), the parse error has been reported to the collector
+pub struct Context<'a, T> {
+    collector: &'a mut Collector,
+    data: Option<Data<'a, T>>,
+    poisoned: bool,
+}
+
+impl<'a, A, I> Context<'a, (A, I)>
+where
+    A: Parse,
+    I: Parse,
+{
+    /// try to parse the arguments and the item of an attribute macro from [`proc_macro::TokenStream`]s
+    ///
+    /// if parsing the arguments or the item fails the errors are reported to the collector and the context has no data
+    pub fn new_parse_attr(
+        collector: &'a mut Collector,
+        attr: proc_macro::TokenStream,
+        item: proc_macro::TokenStream,
+    ) -> Self {
+        Self::from_results(collector, parse(attr), parse(item))
+    }
+
+    /// try to parse the arguments and the item of an attribute macro from [`proc_macro2::TokenStream`]s
+    ///
+    /// if parsing the arguments or the item fails the errors are reported to the collector and the context has no data
+    pub fn new_parse_attr2(
+        collector: &'a mut Collector,
+        attr: TokenStream,
+        item: TokenStream,
+    ) -> Self {
+        Self::from_results(collector, parse2(attr), parse2(item))
+    }
+
+    fn from_results(
+        collector: &'a mut Collector,
+        attr: Result<A, Error>,
+        item: Result<I, Error>,
+    ) -> Self {
+        match (attr, item) {
+            (Ok(attr), Ok(item)) => Self::new(collector, (attr, item)),
+            (Err(attr), Err(item)) => {
+                collector.error(attr);
+                Self::new_poisoned(collector, item)
+            }
+            (Err(e), _) | (_, Err(e)) => Self::new_poisoned(collector, e),
+        }
+    }
+}
+
+impl Context<'_, DeriveInput> {
+    /// run `f` with a child context for every variant of an enum
+    ///
+    /// the child contexts report to the same collector.
+    /// nothing happens if the input isn't an enum or the context has no data, use a `Lint` to report an error for other inputs
+    pub fn for_each_variant(&mut self, mut f: impl FnMut(&mut Context<'_, Variant>)) {
+        if let Some(syn::Data::Enum(e)) = self.data.as_deref().map(|input| &input.data) {
+            for variant in e.variants.iter() {
+                f(&mut Context::new_by_ref(self.collector, variant));
+            }
+        }
+    }
+
+    /// run `f` with the index of the field and a child context for every field of a struct
+    ///
+    /// the child contexts report to the same collector.
+    /// nothing happens if the input isn't a struct or the context has no data, use a `Lint` to report an error for other inputs
+    pub fn for_each_field(&mut self, mut f: impl FnMut(usize, &mut Context<'_, Field>)) {
+        if let Some(syn::Data::Struct(s)) = self.data.as_deref().map(|input| &input.data) {
+            for (i, field) in s.fields.iter().enumerate() {
+                f(i, &mut Context::new_by_ref(self.collector, field));
+            }
+        }
+    }
+
+    /// collect and parse the helper attributes with the name, eg. `"from_str"` for `#[from_str(...)]`
+    ///
+    /// the attributes of the item are collected first, followed by the attributes of the variants and fields in the order they appear in the input.
+    /// the fields of structs, enum variants and unions are all included. malformed attributes are reported to the collector with the span of the attribute and skipped.
+    /// returns an empty `Vec` if the context has no data
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context};
+    /// use syn::{parse_quote, DeriveInput};
+    ///
+    /// let input: DeriveInput = parse_quote!(
+    ///     #[from_str(ignore_case)]
+    ///     enum Foo {
+    ///         #[from_str(rename = "bar")]
+    ///         Bar,
+    ///         #[from_str = ]
+    ///         Baz,
+    ///     }
+    /// );
+    ///
+    /// let mut collector = Collector::new();
+    /// let attrs = Context::new(&mut collector, input).helper_attrs("from_str");
+    /// assert_eq!(attrs.len(), 2);
+    /// assert_eq!(collector.error_count(), 1);
+    /// ```
+    pub fn helper_attrs(&mut self, name: &str) -> Vec<(Span, Meta)> {
+        let input = match self.data.as_deref() {
+            Some(input) => input,
+            None => return Vec::new(),
+        };
+        let mut attrs: Vec<&Attribute> = input.attrs.iter().collect();
+        match &input.data {
+            syn::Data::Struct(s) => attrs.extend(s.fields.iter().flat_map(|field| &field.attrs)),
+            syn::Data::Enum(e) => {
+                for variant in e.variants.iter() {
+                    attrs.extend(&variant.attrs);
+                    attrs.extend(variant.fields.iter().flat_map(|field| &field.attrs));
+                }
+            }
+            syn::Data::Union(u) => {
+                attrs.extend(u.fields.named.iter().flat_map(|field| &field.attrs))
+            }
+        }
+        parse_helper_attrs(self.collector, attrs, name)
+    }
+
+    /// expand the macro if the input has an attribute with the path, eg. `"from_str"` for `#[from_str(...)]`
+    ///
+    /// paths with multiple segments are written with `::`, eg. `"foo::bar"`
+    pub fn expand_if_attr(&mut self, path: &str, expand: &impl Expand<DeriveInput>) {
+        self.expand_if(
+            |input| input.attrs.iter().any(|attr| path_eq(&attr.path, path)),
+            expand,
+        );
+    }
+}
+
+/// run `f` and record its duration under the type name of `P` if the `timings` feature is enabled
+#[cfg(feature = "timings")]
+fn timed<P: ?Sized, R>(c: &mut Collector, f: impl FnOnce(&mut Collector) -> R) -> R {
+    timed_as(c, type_name::<P>(), f)
+}
+
+#[cfg(not(feature = "timings"))]
+#[inline(always)]
+#[allow(clippy::extra_unused_type_parameters)]
+fn timed<P: ?Sized, R>(c: &mut Collector, f: impl FnOnce(&mut Collector) -> R) -> R {
+    f(c)
+}
+
+/// run `f` and record its duration under `name`
+///
+/// this is used for type erased passes, whose type name isn't known statically
+#[cfg(feature = "timings")]
+fn timed_as<R>(c: &mut Collector, name: &str, f: impl FnOnce(&mut Collector) -> R) -> R {
+    let start = Instant::now();
+    let res = f(c);
+    c.timings.push((name.to_string(), start.elapsed()));
+    res
+}
+
+impl Context<'_, Variant> {
+    /// collect and parse the helper attributes of the variant with the name, see [`Context::<DeriveInput>::helper_attrs`]
+    ///
+    /// only the attributes of the variant itself are collected, not the ones of its fields
+    pub fn helper_attrs(&mut self, name: &str) -> Vec<(Span, Meta)> {
+        match self.data.as_deref() {
+            Some(variant) => parse_helper_attrs(self.collector, &variant.attrs, name),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl Context<'_, Field> {
+    /// collect and parse the helper attributes of the field with the name, see [`Context::<DeriveInput>::helper_attrs`]
+    pub fn helper_attrs(&mut self, name: &str) -> Vec<(Span, Meta)> {
+        match self.data.as_deref() {
+            Some(field) => parse_helper_attrs(self.collector, &field.attrs, name),
+            None => Vec::new(),
+        }
+    }
+}
+
+fn parse_helper_attrs<'b>(
+    c: &mut Collector,
+    attrs: impl IntoIterator<Item = &'b Attribute>,
+    name: &str,
+) -> Vec<(Span, Meta)> {
+    let mut metas = Vec::new();
+    for attr in attrs {
+        if !path_eq(&attr.path, name) {
+            continue;
+        }
+        match attr.parse_meta() {
+            Ok(meta) => metas.push((attr.span(), meta)),
+            Err(e) => c.error_spanned(attr, format!("malformed `{}` attribute: {}", name, e)),
+        }
+    }
+    metas
+}
+
+fn path_eq(path: &Path, s: &str) -> bool {
+    let mut segments = s.split("::");
+    path.leading_colon.is_none()
+        && path
+            .segments
+            .iter()
+            .all(|segment| segments.next().is_some_and(|s| segment.ident == s))
+        && segments.next().is_none()
+}
+
+/// shows the collector and whether the context has data
+impl<T> Debug for Context<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("collector", &self.collector)
+            .field("has_data", &self.data.is_some())
+            .finish()
+    }
+}
+
+impl<'a, T> Context<'a, T> {
+    /// create a new context with the data
+    pub fn new(collector: &'a mut Collector, data: T) -> Self {
+        Context {
+            collector,
+            data: Some(Data::Owned(data)),
+            poisoned: false,
+        }
+    }
+
+    /// create a new context with the data passed by reference
+    pub fn new_by_ref(collector: &'a mut Collector, data: &'a T) -> Self {
+        Context {
+            collector,
+            data: Some(Data::Borrowed(data)),
+            poisoned: false,
+        }
+    }
+
+    /// create a new context without any data in it
+    pub fn new_empty(collector: &'a mut Collector) -> Self {
+        Context {
+            collector,
+            data: None,
+            poisoned: false,
+        }
+    }
+
+    /// create a poisoned context without data and report the error
+    fn new_poisoned(collector: &'a mut Collector, e: Error) -> Self {
+        collector.error(e);
+        Context {
+            collector,
+            data: None,
+            poisoned: true,
+        }
+    }
+
+    /// create a context with borrowed data that is poisoned if `poisoned` is true
+    pub(crate) fn from_parts(
+        collector: &'a mut Collector,
+        data: Option<&'a T>,
+        poisoned: bool,
+    ) -> Self {
+        Context {
+            collector,
+            data: data.map(Data::Borrowed),
+            poisoned,
+        }
+    }
+
+    /// try to parse the data from a [`proc_macro::TokenStream`]
+    ///
+    /// if parsing the data fails the error is reported to the collector
+    pub fn new_parse(collector: &'a mut Collector, data: proc_macro::TokenStream) -> Self
+    where
+        T: Parse,
+    {
+        match parse::<T>(data) {
+            Ok(data) => Self::new(collector, data),
+            Err(e) => Self::new_poisoned(collector, e),
+        }
+    }
+
+    /// try to parse the data from a [`proc_macro2::TokenStream`]
+    ///
+    /// if parsing the data fails the error is reported to the collector
+    pub fn new_parse2(collector: &'a mut Collector, data: TokenStream) -> Self
+    where
+        T: Parse,
+    {
+        match parse2::<T>(data) {
+            Ok(data) => Self::new(collector, data),
+            Err(e) => Self::new_poisoned(collector, e),
+        }
+    }
+
+    /// create a context by parsing the tokens of another value
+    ///
+    /// this can be used to lint or expand a value as another type, eg. an `ItemConst` as an `Item`.
+    /// the spans of the tokens are preserved. if parsing fails the error is reported to the collector
+    pub fn new_from_tokens<S: ToTokens + ?Sized>(collector: &'a mut Collector, src: &S) -> Self
+    where
+        T: Parse,
+    {
+        Self::new_parse2(collector, src.to_token_stream())
+    }
+
+    /// checks if the context has data
+    pub fn has_data(&self) -> bool {
+        self.data.is_some()
+    }
+
+    /// checks if the context has no data because parsing it failed
+    ///
+    /// this is also true if the data couldn't be transformed with [`Context::try_map`].
+    /// unlike [`Collector::has_errors`] this isn't affected by errors reported by other contexts
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// the data of the context
+    ///
+    /// returns `None` if the context has no data, eg. because parsing it failed
+    pub fn data(&self) -> Option<&T> {
+        self.data.as_deref()
+    }
+
+    /// the data of the context as a mutable reference
+    ///
+    /// returns `None` if the context has no data or if the data was passed by reference with [`Context::new_by_ref`]
+    pub fn data_mut(&mut self) -> Option<&mut T> {
+        match self.data.as_mut()? {
+            Data::Owned(data) => Some(data),
+            Data::Borrowed(_) => None,
+        }
+    }
+
+    /// replace the data of the context
+    ///
+    /// this also works for empty contexts and contexts with borrowed data, afterwards the context owns the data
+    pub fn set(&mut self, data: T) {
+        self.data = Some(Data::Owned(data));
+    }
+
+    /// move the data out of the context
+    ///
+    /// afterwards the context has no data until it is put back with [`Context::put_data`]. the collector and the error gate aren't affected.
+    /// returns `None` and leaves the data in place if the data was passed by reference with [`Context::new_by_ref`], use [`Context::update`] to transform owned data in one step
+    pub fn take_data(&mut self) -> Option<T> {
+        match self.data.take() {
+            Some(Data::Owned(data)) => Some(data),
+            data => {
+                self.data = data;
+                None
+            }
         }
     }
 
-    /// report an error
+    /// put data back into the context, eg. after it was moved out with [`Context::take_data`]
     ///
-    /// once an error has been reported to an collector, `Expand`s will no longer be run
-    pub fn error(&mut self, e: Error) {
-        let error: TokenStream = e.to_compile_error();
-        self.output.extend(error);
-        self.err_count += 1;
+    /// this is the same as [`Context::set`]
+    pub fn put_data(&mut self, data: T) {
+        self.set(data);
     }
 
-    /// checks if any errors have been reported yet
-    pub fn has_errors(&self) -> bool {
-        self.err_count != 0
+    /// replace the data with the result of `f`
+    ///
+    /// returns false without calling `f` if the context has no data or if the data was passed by reference with [`Context::new_by_ref`]
+    pub fn update(&mut self, f: impl FnOnce(T) -> T) -> bool {
+        match self.data.take() {
+            Some(Data::Owned(data)) => {
+                self.set(f(data));
+                true
+            }
+            data => {
+                self.data = data;
+                false
+            }
+        }
     }
 
-    /// finish the expansion and return the result
-    pub fn finish(self) -> TokenStream {
-        self.output
+    /// modify the data in place
+    ///
+    /// returns false without calling `f` if the context has no data or if the data was passed by reference with [`Context::new_by_ref`]
+    pub fn modify(&mut self, f: impl FnOnce(&mut T)) -> bool {
+        match self.data_mut() {
+            Some(data) => {
+                f(data);
+                true
+            }
+            None => false,
+        }
     }
-}
 
-impl Default for Collector {
-    fn default() -> Self {
-        Self::new()
+    /// take the data out of the context
+    ///
+    /// returns `None` if the context has no data or if the data was passed by reference with [`Context::new_by_ref`], use [`Context::to_inner`] to clone the data instead.
+    /// the collector can be used again afterwards
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context};
+    /// use quote::quote;
+    /// use syn::DeriveInput;
+    ///
+    /// let mut collector = Collector::new();
+    /// let ctx = Context::<DeriveInput>::new_parse2(&mut collector, quote!(struct Foo;));
+    /// let mut input = ctx.into_inner().unwrap();
+    ///
+    /// input.ident = syn::parse_quote!(Bar);
+    ///
+    /// let ctx = Context::new(&mut collector, input);
+    /// assert_eq!(ctx.data().unwrap().ident, "Bar");
+    /// ```
+    pub fn into_inner(self) -> Option<T> {
+        match self.data? {
+            Data::Owned(data) => Some(data),
+            Data::Borrowed(_) => None,
+        }
     }
-}
 
-enum Data<'a, T> {
-    Owned(T),
-    Borrowed(&'a T),
-}
+    /// clone the data out of the context
+    ///
+    /// unlike [`Context::into_inner`] this also returns data passed by reference.
+    /// returns `None` if the context has no data
+    pub fn to_inner(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.data().cloned()
+    }
 
-impl<T> Deref for Data<'_, T> {
-    type Target = T;
+    /// remove the data if `pred` returns false for it
+    ///
+    /// afterwards `Lint`s and `Expand`s of the context silently do nothing, no error is reported.
+    /// returns whether the context still has data, `pred` isn't called if the context has no data
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context, EchoExpand};
+    /// use syn::{parse_quote, Item};
+    ///
+    /// let mut collector = Collector::new();
+    /// let input: Item = parse_quote!(struct Foo;);
+    ///
+    /// let mut structs = Context::new_by_ref(&mut collector, &input);
+    /// if structs.filter(|item| matches!(item, Item::Struct(_))) {
+    ///     structs.expand(&EchoExpand);
+    /// }
+    ///
+    /// let mut enums = Context::new_by_ref(&mut collector, &input);
+    /// assert!(!enums.filter(|item| matches!(item, Item::Enum(_))));
+    /// enums.expand(&EchoExpand);
+    ///
+    /// assert_eq!(collector.finish().to_string(), "struct Foo ;");
+    /// ```
+    pub fn filter(&mut self, pred: impl FnOnce(&T) -> bool) -> bool {
+        if !self.data().is_some_and(pred) {
+            self.data = None;
+        }
+        self.has_data()
+    }
 
-    fn deref(&self) -> &Self::Target {
-        match self {
-            Data::Owned(data) => &data,
-            Data::Borrowed(data) => data,
+    /// convert a context with data passed by reference into one that owns the data
+    ///
+    /// this clones the data if it was passed by reference with [`Context::new_by_ref`], contexts that already own their data are returned as is.
+    /// the context still borrows the collector. prefer borrowed contexts and only detach when the data is needed after the borrow ends,
+    /// eg. for [`Context::into_inner`] or [`Context::data_mut`]
+    pub fn detach(self) -> Self
+    where
+        T: Clone,
+    {
+        let data = match self.data {
+            Some(Data::Borrowed(data)) => Some(Data::Owned(data.clone())),
+            data => data,
+        };
+        Context {
+            collector: self.collector,
+            data,
+            poisoned: self.poisoned,
         }
     }
-}
 
-/// used to lint, expand and capture
-///
-/// a context might not have data in it (eg. if parsing the data failed), so calling the lint and expand functions does not guarantee the `Lint`s and `Expand`s actually run
-pub struct Context<'a, T> {
-    collector: &'a mut Collector,
-    data: Option<Data<'a, T>>,
-}
+    /// run `f` with a child context for other data
+    ///
+    /// the child context reports to the same collector, so errors reported in `f` stop later `Expand`s of this context from running.
+    /// the data of this context is still available afterwards
+    pub fn fork<U, R>(&mut self, data: U, f: impl FnOnce(&mut Context<'_, U>) -> R) -> R {
+        f(&mut Context::new(self.collector, data))
+    }
 
-impl<'a, T> Context<'a, T> {
-    /// create a new context with the data
-    pub fn new(collector: &'a mut Collector, data: T) -> Self {
+    /// run `f` with a child context for a part of the data
+    ///
+    /// the child context borrows the part returned by `project` and reports to the same collector, so no data is cloned.
+    /// returns `None` without calling `project` or `f` if this context has no data
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context, Lint};
+    /// use syn::{parse_quote, DeriveInput, Generics};
+    ///
+    /// struct NoGenericsLint;
+    ///
+    /// impl Lint<Generics> for NoGenericsLint {
+    ///     fn lint(&self, input: &Generics, c: &mut Collector) {
+    ///         if !input.params.is_empty() {
+    ///             c.error_spanned(input, "generics are not supported");
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut collector = Collector::new();
+    /// let input: DeriveInput = parse_quote!(struct Foo<T>(T););
+    /// let mut ctx = Context::new(&mut collector, input);
+    /// let res = ctx.enter(|input| &input.generics, |ctx| ctx.lint(&NoGenericsLint));
+    /// assert_eq!(res, Some(false));
+    /// ```
+    pub fn enter<U, R>(
+        &mut self,
+        project: impl FnOnce(&T) -> &U,
+        f: impl FnOnce(&mut Context<'_, U>) -> R,
+    ) -> Option<R> {
+        let data = self.data.as_deref()?;
+        Some(f(&mut Context::new_by_ref(self.collector, project(data))))
+    }
+
+    /// transform the data into a new context
+    ///
+    /// the new context reports to the same collector. if this context has no data, `f` isn't called and the new context has no data either
+    pub fn map<U>(self, f: impl FnOnce(&T) -> U) -> Context<'a, U> {
         Context {
-            collector,
-            data: Some(Data::Owned(data)),
+            collector: self.collector,
+            data: self.data.map(|data| Data::Owned(f(&data))),
+            poisoned: self.poisoned,
         }
     }
 
-    /// create a new context with the data passed by reference
-    pub fn new_by_ref(collector: &'a mut Collector, data: &'a T) -> Self {
+    /// combine the data with other data into a context over a tuple
+    ///
+    /// the new context reports to the same collector and has no data if this context has no data or `other` is `None`.
+    /// a second context can't borrow the collector at the same time as this one, so the other data is passed directly, eg. the result of [`Context::capture`].
+    /// owned data is moved into the tuple, data passed by reference with [`Context::new_by_ref`] is cloned
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context, Expand};
+    /// use proc_macro2::Ident;
+    /// use quote::format_ident;
+    /// use syn::{parse_quote, DeriveInput, ItemImpl};
+    ///
+    /// struct Config {
+    ///     error: Ident,
+    /// }
+    ///
+    /// struct ImplExpand;
+    ///
+    /// impl Expand<(DeriveInput, Config)> for ImplExpand {
+    ///     type Output = ItemImpl;
+    ///
+    ///     fn expand(&self, (input, config): &(DeriveInput, Config), _: &mut Collector) -> Option<Self::Output> {
+    ///         let ident = &input.ident;
+    ///         let error = &config.error;
+    ///         Some(parse_quote!(impl Foo for #ident { type Error = #error; }))
+    ///     }
+    /// }
+    ///
+    /// let mut collector = Collector::new();
+    /// let input: DeriveInput = parse_quote!(struct Bar;);
+    /// let config = Config { error: format_ident!("BarError") };
+    ///
+    /// let mut ctx = Context::new(&mut collector, input).zip(Some(config));
+    /// ctx.expand(&ImplExpand);
+    /// assert_eq!(collector.finish().to_string(), "impl Foo for Bar { type Error = BarError ; }");
+    /// ```
+    pub fn zip<U>(self, other: Option<U>) -> Context<'a, (T, U)>
+    where
+        T: Clone,
+    {
+        let data = match (self.data, other) {
+            (Some(Data::Owned(data)), Some(other)) => Some(Data::Owned((data, other))),
+            (Some(Data::Borrowed(data)), Some(other)) => Some(Data::Owned((data.clone(), other))),
+            _ => None,
+        };
         Context {
-            collector,
-            data: Some(Data::Borrowed(data)),
+            collector: self.collector,
+            data,
+            poisoned: self.poisoned,
         }
     }
 
-    /// create a new context without any data in it
-    pub fn new_empty(collector: &'a mut Collector) -> Self {
-        Context {
-            collector,
-            data: None,
+    /// try to transform the data into a new context
+    ///
+    /// if `f` fails the error is reported to the collector and the new context has no data
+    pub fn try_map<U>(self, f: impl FnOnce(&T) -> Result<U, Error>) -> Context<'a, U> {
+        match self.data.map(|data| f(&data)) {
+            Some(Ok(data)) => Context::new(self.collector, data),
+            Some(Err(e)) => Context::new_poisoned(self.collector, e),
+            None => Context {
+                collector: self.collector,
+                data: None,
+                poisoned: self.poisoned,
+            },
         }
     }
 
-    /// try to parse the data from a [`proc_macro::TokenStream`]
+    /// try to parse the data from a [`proc_macro::TokenStream`] and keep the tokens if parsing fails
     ///
-    /// if parsing the data fails the error is reported to the collector
-    pub fn new_parse(collector: &'a mut Collector, data: proc_macro::TokenStream) -> Self
+    /// if parsing the data fails the error is reported to the collector and the original tokens are added to the output.
+    /// this is useful for attribute macros, where the item would otherwise disappear and cause follow-up errors
+    pub fn new_parse_preserving(collector: &'a mut Collector, data: proc_macro::TokenStream) -> Self
     where
         T: Parse,
     {
-        match parse::<T>(data) {
+        Self::new_parse2_preserving(collector, data.into())
+    }
+
+    /// try to parse the data from a [`proc_macro2::TokenStream`] and keep the tokens if parsing fails
+    ///
+    /// see [`Context::new_parse_preserving`]
+    pub fn new_parse2_preserving(collector: &'a mut Collector, data: TokenStream) -> Self
+    where
+        T: Parse,
+    {
+        match parse2::<T>(data.clone()) {
             Ok(data) => Self::new(collector, data),
             Err(e) => {
-                collector.error(e);
-                Self {
-                    collector,
-                    data: None,
-                }
+                collector.push_tokens(data);
+                Self::new_poisoned(collector, e)
             }
         }
     }
 
-    /// try to parse the data from a [`proc_macro2::TokenStream`]
+    /// try to parse the data from a [`proc_macro::TokenStream`] with a custom parser
     ///
     /// if parsing the data fails the error is reported to the collector
-    pub fn new_parse2(collector: &'a mut Collector, data: TokenStream) -> Self
+    pub fn new_parse_with(
+        collector: &'a mut Collector,
+        data: proc_macro::TokenStream,
+        parser: impl Parser<Output = T>,
+    ) -> Self {
+        match parser.parse(data) {
+            Ok(data) => Self::new(collector, data),
+            Err(e) => Self::new_poisoned(collector, e),
+        }
+    }
+
+    /// try to parse the data from a [`proc_macro2::TokenStream`] with a custom parser
+    ///
+    /// if parsing the data fails the error is reported to the collector
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context};
+    /// use quote::quote;
+    /// use syn::{punctuated::Punctuated, Ident, Token};
+    ///
+    /// let mut collector = Collector::new();
+    /// let ctx = Context::new_parse2_with(
+    ///     &mut collector,
+    ///     quote!(foo, bar, baz),
+    ///     Punctuated::<Ident, Token![,]>::parse_terminated,
+    /// );
+    /// assert_eq!(ctx.data().unwrap().len(), 3);
+    /// ```
+    pub fn new_parse2_with(
+        collector: &'a mut Collector,
+        data: TokenStream,
+        parser: impl Parser<Output = T>,
+    ) -> Self {
+        match parser.parse2(data) {
+            Ok(data) => Self::new(collector, data),
+            Err(e) => Self::new_poisoned(collector, e),
+        }
+    }
+
+    /// try to parse the data from a string
+    ///
+    /// if the string isn't valid rust tokens or parsing the data fails the error is reported to the collector.
+    /// unlike [`Context::new_parse`] this can be used outside of a proc-macro, eg. in tests
+    pub fn new_parse_str(collector: &'a mut Collector, src: &str) -> Self
     where
         T: Parse,
     {
-        match parse2::<T>(data) {
+        match syn::parse_str::<T>(src) {
             Ok(data) => Self::new(collector, data),
-            Err(e) => {
-                collector.error(e);
-                Self {
-                    collector,
-                    data: None,
-                }
-            }
+            Err(e) => Self::new_poisoned(collector, e),
         }
     }
 
+    /// allow `Expand`s to run again even though errors have been reported, see [`Collector::recover`]
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context, EchoExpand};
+    /// use proc_macro2::Span;
+    /// use syn::{parse_quote, Error, ItemConst};
+    ///
+    /// let mut collector = Collector::new();
+    /// collector.error(Error::new(Span::call_site(), "invalid attribute"));
+    ///
+    /// let input: ItemConst = parse_quote!(const FOO: u8 = 0;);
+    /// let mut ctx = Context::new(&mut collector, input);
+    /// ctx.recover();
+    /// ctx.expand(&EchoExpand);
+    ///
+    /// assert!(collector.has_errors());
+    /// assert!(collector.finish().to_string().contains("const FOO"));
+    /// ```
+    pub fn recover(&mut self) {
+        self.collector.recover();
+    }
+
+    /// lint the macro input and mention the name of the lint in its errors
+    ///
+    /// the messages of errors reported by the lint are suffixed with ``"(while linting `{name}`)"`` when they're rendered, the spans of the errors are preserved.
+    /// names of nested passes are stacked, eg. ``"(while linting `inner`, while expanding `outer`)"``
+    pub fn lint_named<L: Lint<T> + ?Sized>(&mut self, name: &str, lint: &L) -> bool {
+        let len = self.collector.passes.len();
+        self.collector.passes.push(format!("linting `{}`", name));
+        let res = self.lint(lint);
+        self.collector.passes.truncate(len);
+        res
+    }
+
     /// lint the macro input
     ///
     /// returns true if the lint ran without reporting an error
-    pub fn lint<L: Lint<T>>(&mut self, lint: &L) -> bool {
-        if let Some(data) = self.data.take() {
-            let start = self.collector.err_count;
-            lint.lint(&*data, &mut self.collector);
-            self.data = Some(data);
-            start == self.collector.err_count
-        } else {
-            false
+    pub fn lint<L: Lint<T> + ?Sized>(&mut self, lint: &L) -> bool {
+        self.lint_counted(lint) == Some(0)
+    }
+
+    /// lint the macro input
+    ///
+    /// returns the number of errors reported by the lint or `None` if the context has no data or the lint was skipped, see [`Collector::set_lint_gating`]
+    pub fn lint_counted<L: Lint<T> + ?Sized>(&mut self, lint: &L) -> Option<usize> {
+        if self.collector.skips_lints() {
+            return None;
+        }
+        let data = self.data.take()?;
+        let start = self.collector.error_count();
+        timed::<L, _>(self.collector, |c| lint.lint(&data, c));
+        self.data = Some(data);
+        Some(self.collector.error_count() - start)
+    }
+
+    /// analyze the macro input and return the result
+    ///
+    /// unlike [`Context::capture`] this also runs after errors have been reported. errors reported by the analysis stop later `Expand`s from running as usual
+    pub fn analyze<A: Analyze<T> + ?Sized>(&mut self, analyze: &A) -> Option<A::Output> {
+        let data = self.data.as_ref()?;
+        timed::<A, _>(self.collector, |c| analyze.analyze(data, c))
+    }
+
+    /// analyze the macro input and store the result for the key
+    ///
+    /// the result can be used by later `Expand`s with [`WithCaptured`](crate::WithCaptured) or [`Collector::captured`].
+    /// returns true if the analysis produced a result
+    pub fn analyze_into<K, A>(&mut self, analyze: &A) -> bool
+    where
+        K: CaptureKey,
+        A: Analyze<T, Output = K::Value> + ?Sized,
+    {
+        match self.analyze(analyze) {
+            Some(value) => {
+                self.collector.set_captured::<K>(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// lint the macro input with a lint that keeps state between invocations
+    ///
+    /// returns true if the lint ran without reporting an error
+    pub fn lint_mut<L: LintMut<T> + ?Sized>(&mut self, lint: &mut L) -> bool {
+        let data = match self.data.as_ref() {
+            Some(data) if !self.collector.skips_lints() => data,
+            _ => return false,
+        };
+        let start = self.collector.error_count();
+        timed::<L, _>(self.collector, |c| lint.lint_mut(data, c));
+        self.collector.error_count() == start
+    }
+
+    /// lint the macro input and return the errors reported by the lint
+    ///
+    /// the errors are still reported to the collector. errors discarded by deduplication aren't returned
+    pub fn lint_collect<L: Lint<T> + ?Sized>(&mut self, lint: &L) -> Vec<Error> {
+        let start = self.collector.errors().len();
+        self.lint(lint);
+        self.collector.errors().skip(start).cloned().collect()
+    }
+
+    /// run all lints
+    ///
+    /// unlike chaining [`Context::lint`] with `&&` all lints are run, even if one of them reports an error.
+    /// returns true if all lints ran without reporting an error
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context, Lint};
+    /// use syn::{parse_quote, DeriveInput};
+    ///
+    /// struct NoGenericsLint;
+    ///
+    /// impl Lint<DeriveInput> for NoGenericsLint {
+    ///     fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+    ///         if !input.generics.params.is_empty() {
+    ///             c.error_spanned(&input.generics, "generics are not supported");
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// struct NoAttributesLint;
+    ///
+    /// impl Lint<DeriveInput> for NoAttributesLint {
+    ///     fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+    ///         for attr in input.attrs.iter() {
+    ///             c.error_spanned(attr, "attributes are not supported");
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let input: DeriveInput = parse_quote!(
+    ///     #[foo]
+    ///     struct Foo<T>(T);
+    /// );
+    ///
+    /// let mut collector = Collector::new();
+    /// let mut ctx = Context::new(&mut collector, input);
+    /// assert!(!ctx.lint_all(&[&NoGenericsLint, &NoAttributesLint]));
+    /// assert_eq!(collector.error_count(), 2);
+    /// ```
+    pub fn lint_all(&mut self, lints: &[&dyn Lint<T>]) -> bool {
+        let mut passed = true;
+        for lint in lints {
+            passed &= self.lint(*lint);
+        }
+        passed
+    }
+
+    /// run a pass and add the generated tokens to the collector
+    ///
+    /// see [`LintPass`](crate::LintPass) and [`ExpandPass`](crate::ExpandPass)
+    pub fn run<P: Pass<T> + ?Sized>(&mut self, pass: &P) {
+        if let Some(data) = self.data.as_ref() {
+            #[cfg(feature = "timings")]
+            let tokens = timed_as(self.collector, pass.type_name(), |c| pass.run(data, c));
+            #[cfg(not(feature = "timings"))]
+            let tokens = pass.run(data, self.collector);
+            if let Some(tokens) = tokens {
+                self.collector.push_tokens(tokens);
+            }
+        }
+    }
+
+    /// run all passes in order
+    ///
+    /// lints always run, expansions are skipped once an error has been reported
+    pub fn run_all(&mut self, passes: &[&dyn Pass<T>]) {
+        for pass in passes {
+            self.run(*pass);
         }
     }
 
+    /// expand the macro, add the result to the collector and mention the name of the expand in its errors
+    ///
+    /// the messages of errors reported by the expand are suffixed with ``"(while expanding `{name}`)"`` when they're rendered, the spans of the errors are preserved.
+    /// names of nested passes are stacked, eg. ``"(while expanding `inner`, while expanding `outer`)"``
+    pub fn expand_named(&mut self, name: &str, expand: &impl Expand<T>) {
+        let len = self.collector.passes.len();
+        self.collector.passes.push(format!("expanding `{}`", name));
+        self.expand(expand);
+        self.collector.passes.truncate(len);
+    }
+
     /// expand the macro and add the result to the collector
     pub fn expand(&mut self, expand: &impl Expand<T>) {
         if let Some(res) = self.capture(expand) {
             let tts: TokenStream = res.to_token_stream();
-            self.collector.output.extend(tts);
+            self.collector.push_tokens(tts);
+        }
+    }
+
+    /// expand the macro into multiple items and add them to the collector in order
+    pub fn expand_iter(&mut self, expand: &impl ExpandIter<T>) {
+        for item in self.capture_iter(expand).into_iter().flatten() {
+            self.collector.push_tokens(item.into_token_stream());
+        }
+    }
+
+    /// expand the macro into multiple items and return them
+    pub fn capture_iter<E: ExpandIter<T>>(&mut self, expand: &E) -> Option<Vec<E::Item>> {
+        if self.collector.has_new_errors() {
+            return None;
+        }
+        let data = self.data.as_ref()?;
+        timed::<E, _>(self.collector, |c| expand.expand_iter(data, c))
+    }
+
+    /// expand the macro with an `Expand` that keeps state between invocations and add the result to the collector
+    pub fn expand_mut(&mut self, expand: &mut impl ExpandMut<T>) {
+        if let Some(res) = self.capture_mut(expand) {
+            self.collector.push_tokens(res.into_token_stream());
+        }
+    }
+
+    /// expand the macro with an `Expand` that keeps state between invocations and return the output
+    pub fn capture_mut<E: ExpandMut<T> + ?Sized>(&mut self, expand: &mut E) -> Option<E::Output> {
+        if self.collector.has_new_errors() {
+            return None;
         }
+        let data = self.data.as_ref()?;
+        timed::<E, _>(self.collector, |c| expand.expand_mut(data, c))
+    }
+
+    /// expand the macro if `cond` returns true for the data
+    ///
+    /// `cond` isn't called if the context has no data
+    pub fn expand_if(&mut self, cond: impl FnOnce(&T) -> bool, expand: &impl Expand<T>) {
+        if self.data().is_some_and(cond) {
+            self.expand(expand);
+        }
+    }
+
+    /// expand the macro with all `Expand`s and add the results to the collector in order
+    ///
+    /// like with [`Context::expand`] the remaining `Expand`s are skipped once an error has been reported
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context, EchoExpand, Expand};
+    /// use syn::{parse_quote, DeriveInput, Ident};
+    ///
+    /// struct IdentExpand;
+    ///
+    /// impl Expand<DeriveInput> for IdentExpand {
+    ///     type Output = Ident;
+    ///
+    ///     fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
+    ///         Some(input.ident.clone())
+    ///     }
+    /// }
+    ///
+    /// let input: DeriveInput = parse_quote!(struct Foo;);
+    ///
+    /// let mut collector = Collector::new();
+    /// let mut ctx = Context::new(&mut collector, input);
+    /// ctx.expand_all(&[&EchoExpand, &IdentExpand]);
+    /// assert_eq!(collector.finish().to_string(), "struct Foo ; Foo");
+    /// ```
+    pub fn expand_all(&mut self, expands: &[&dyn ErasedExpand<T>]) {
+        for tokens in self.capture_all(expands) {
+            self.collector.push_tokens(tokens);
+        }
+    }
+
+    /// expand the macro with all `Expand`s and return the outputs in order
+    ///
+    /// `Expand`s that don't produce an output are skipped, so the result might contain fewer token streams than there are `Expand`s.
+    /// like with [`Context::capture`] the remaining `Expand`s are skipped once an error has been reported
+    pub fn capture_all(&mut self, expands: &[&dyn ErasedExpand<T>]) -> Vec<TokenStream> {
+        let mut outputs = Vec::new();
+        for expand in expands {
+            if self.collector.has_new_errors() {
+                break;
+            }
+            if let Some(data) = self.data.as_ref() {
+                #[cfg(feature = "timings")]
+                let tokens = timed_as(self.collector, expand.type_name(), |c| {
+                    expand.expand_erased(data, c)
+                });
+                #[cfg(not(feature = "timings"))]
+                let tokens = expand.expand_erased(data, self.collector);
+                outputs.extend(tokens);
+            }
+        }
+        outputs
+    }
+
+    /// expand the macro and add the result to a named section of the collector, see [`Collector::append_to`]
+    pub fn expand_into_section(&mut self, section: &str, expand: &impl Expand<T>) {
+        if let Some(res) = self.capture(expand) {
+            self.collector.append_to(section, res);
+        }
+    }
+
+    /// expand the macro and store the output for the key
+    ///
+    /// the output isn't added to the collector, but can be used by later `Expand`s with [`WithCaptured`](crate::WithCaptured).
+    /// returns true if a value was captured
+    pub fn capture_into<K, E>(&mut self, expand: &E) -> bool
+    where
+        K: CaptureKey,
+        E: Expand<T, Output = K::Value>,
+    {
+        match self.capture(expand) {
+            Some(value) => {
+                self.collector.set_captured::<K>(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// expand the macro and parse the output as another type
+    ///
+    /// if parsing the output fails the error is reported to the collector
+    pub fn capture_parse<E: Expand<T>, U: Parse>(&mut self, expand: &E) -> Option<U> {
+        let output = self.capture(expand)?;
+        self.collector.ok(parse2(output.into_token_stream()))
     }
 
     /// expand the macro and return the output
+    ///
+    /// returns `None` if the `Expand` didn't run or didn't produce any output, use [`Context::try_capture`] to distinguish these cases
     pub fn capture<E: Expand<T>>(&mut self, expand: &E) -> Option<E::Output> {
-        if self.collector.has_errors() {
-            return None;
+        self.try_capture(expand).into_option()
+    }
+
+    /// expand the macro and add the result to the collector with the spans of all tokens set to `span`
+    ///
+    /// the spans of tokens inside of groups are replaced as well.
+    /// this can be used to make errors in the generated code point at a specific part of the input
+    pub fn expand_respanned(&mut self, span: Span, expand: &impl Expand<T>) {
+        if let Some(res) = self.capture(expand) {
+            self.collector
+                .push_tokens(respan(res.into_token_stream(), span));
         }
-        if let Some(data) = self.data.as_ref() {
-            expand.expand(&*data, &mut self.collector)
-        } else {
-            None
+    }
+
+    /// expand the macro and append the result to `out` instead of the collector
+    ///
+    /// like with [`Context::expand`] the expansion is skipped once an error has been reported. errors are still reported to the collector
+    pub fn expand_to<E: Expand<T>>(&mut self, expand: &E, out: &mut TokenStream) {
+        if let Some(res) = self.capture(expand) {
+            res.to_tokens(out);
+        }
+    }
+
+    /// expand the macro and return the output, even if errors have been reported
+    ///
+    /// this runs even when the collector has errors, so the `Expand` must not assume that lints passed.
+    /// returns `None` if the context has no data
+    pub fn capture_unchecked<E: Expand<T>>(&mut self, expand: &E) -> Option<E::Output> {
+        let data = self.data.as_ref()?;
+        timed::<E, _>(self.collector, |c| expand.expand(data, c))
+    }
+
+    /// like [`Context::capture_unchecked`] but appends the output
+    ///
+    /// this is useful to emit fallback output after lints failed
+    pub fn expand_unchecked(&mut self, expand: &impl Expand<T>) {
+        if let Some(res) = self.capture_unchecked(expand) {
+            self.collector.push_tokens(res.into_token_stream());
+        }
+    }
+
+    /// expand the macro and return the output or the reason why there is none
+    pub fn try_capture<E: Expand<T>>(&mut self, expand: &E) -> CaptureResult<E::Output> {
+        if self.collector.has_new_errors() {
+            return CaptureResult::SkippedDueToErrors;
+        }
+        match self.data.as_ref() {
+            Some(data) => match timed::<E, _>(self.collector, |c| expand.expand(data, c)) {
+                Some(output) => CaptureResult::Produced(output),
+                None => CaptureResult::Nothing,
+            },
+            None => CaptureResult::NoData,
+        }
+    }
+}
+
+/// the result of [`Context::try_capture`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CaptureResult<O> {
+    /// the `Expand` produced an output
+    Produced(O),
+    /// the `Expand` ran, but didn't produce an output
+    Nothing,
+    /// the `Expand` didn't run because errors have been reported
+    SkippedDueToErrors,
+    /// the `Expand` didn't run because the context has no data
+    NoData,
+}
+
+impl<O> CaptureResult<O> {
+    /// the output of the `Expand` if it produced one
+    pub fn into_option(self) -> Option<O> {
+        match self {
+            CaptureResult::Produced(output) => Some(output),
+            _ => None,
         }
     }
 }
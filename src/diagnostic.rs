@@ -0,0 +1,135 @@
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::quote_spanned;
+use std::fmt::Write;
+use syn::Error;
+
+/// additional information attached to an error
+pub(crate) enum Attachment {
+    Note(String),
+    Help(String),
+}
+
+/// render an error and its attachments
+///
+/// with the `nightly-diagnostics` feature errors are emitted through [`proc_macro::Diagnostic`] and nothing is returned.
+/// otherwise the error is rendered as `compile_error!` invocations and the attachments are appended to the first message of the error, see [`attach`]
+pub(crate) fn render_error(e: &Error, attachments: &[Attachment]) -> TokenStream {
+    #[cfg(feature = "nightly-diagnostics")]
+    {
+        if proc_macro::is_available() {
+            let mut messages = e.clone().into_iter();
+            let first = messages.next().expect("an error has at least one message");
+            let mut diagnostic = first.span().unwrap().error(first.to_string());
+            for attachment in attachments {
+                diagnostic = match attachment {
+                    Attachment::Note(note) => diagnostic.note(note.as_str()),
+                    Attachment::Help(help) => diagnostic.help(help.as_str()),
+                };
+            }
+            diagnostic.emit();
+            for e in messages {
+                e.span().unwrap().error(e.to_string()).emit();
+            }
+            return TokenStream::new();
+        }
+    }
+
+    attach(e, attachments).to_compile_error()
+}
+
+/// append the attachments to the first message of the error as `note: ...` and `help: ...` lines
+pub(crate) fn attach(e: &Error, attachments: &[Attachment]) -> Error {
+    if attachments.is_empty() {
+        return e.clone();
+    }
+
+    map_messages(e, |i, mut message| {
+        if i == 0 {
+            for attachment in attachments {
+                let _ = match attachment {
+                    Attachment::Note(note) => write!(message, "\nnote: {}", note),
+                    Attachment::Help(help) => write!(message, "\nhelp: {}", help),
+                };
+            }
+        }
+        message
+    })
+}
+
+/// change the messages of an error while preserving their spans
+///
+/// `f` is called with the index and the text of every message
+pub(crate) fn map_messages(e: &Error, mut f: impl FnMut(usize, String) -> String) -> Error {
+    // `Error` doesn't expose the start and end spans of its messages, but they're used in the compile error
+    let mut spans = Vec::new();
+    let mut start = None;
+    for tt in e.to_compile_error() {
+        let message_start = *start.get_or_insert_with(|| tt.span());
+        if let TokenTree::Group(group) = tt {
+            spans.push((message_start, group.span()));
+            start = None;
+        }
+    }
+
+    let messages = e.clone().into_iter().zip(spans).enumerate();
+    let mut errors = messages.map(|(i, (message, (start, end)))| {
+        let tokens: TokenStream = vec![
+            TokenTree::Ident(Ident::new("start", start)),
+            TokenTree::Ident(Ident::new("end", end)),
+        ]
+        .into_iter()
+        .collect();
+        Error::new_spanned(tokens, f(i, message.to_string()))
+    });
+
+    let mut error = errors.next().expect("an error has at least one message");
+    for e in errors {
+        error.combine(e);
+    }
+    error
+}
+
+/// render a warning and its attachments
+///
+/// with the `nightly-diagnostics` feature the warning is emitted through [`proc_macro::Diagnostic`] and nothing is returned.
+/// otherwise the warning is rendered as a use of a deprecated item, which makes rustc print the message as a `deprecated` warning.
+/// like with [`render_error`] the attachments are appended to the first message.
+/// this is best-effort: the warning might be silenced by `#[allow(deprecated)]` on the surrounding code
+pub(crate) fn render_warning(e: &Error, attachments: &[Attachment]) -> TokenStream {
+    #[cfg(feature = "nightly-diagnostics")]
+    {
+        if proc_macro::is_available() {
+            let mut messages = e.clone().into_iter();
+            let first = messages.next().expect("an error has at least one message");
+            let mut diagnostic = first.span().unwrap().warning(first.to_string());
+            for attachment in attachments {
+                diagnostic = match attachment {
+                    Attachment::Note(note) => diagnostic.note(note.as_str()),
+                    Attachment::Help(help) => diagnostic.help(help.as_str()),
+                };
+            }
+            diagnostic.emit();
+            for e in messages {
+                e.span().unwrap().warning(e.to_string()).emit();
+            }
+            return TokenStream::new();
+        }
+    }
+
+    attach(e, attachments)
+        .into_iter()
+        .map(|e| deprecated_warning(e.span(), &e.to_string()))
+        .collect()
+}
+
+fn deprecated_warning(span: Span, msg: &str) -> TokenStream {
+    let ident = Ident::new("macro_compose_warning", span);
+    quote_spanned!(span=>
+        const _: () = {
+            #[deprecated(note = #msg)]
+            #[allow(non_camel_case_types)]
+            struct #ident;
+            let _ = #ident;
+        };
+    )
+}
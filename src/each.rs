@@ -0,0 +1,126 @@
+use syn::{Data, DeriveInput, Field, Variant};
+
+use crate::{lints::EnsureEnum, Collector, Lint};
+
+/// run a `Lint<Field>` on every field of a struct, of every variant of an enum or of a union
+///
+/// # Example
+/// ```
+/// use macro_compose::{lint_fn, Collector, Context, ForEachField};
+/// use syn::{parse_quote, DeriveInput, Field, Type};
+///
+/// let lint = ForEachField::new(lint_fn(|field: &Field, c| {
+///     if let Type::Reference(_) = &field.ty {
+///         c.error_spanned(&field.ty, "references are not supported");
+///     }
+/// }));
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(struct Foo<'a> { a: &'a u8, b: u8 });
+/// Context::new(&mut collector, input).lint(&lint);
+/// assert_eq!(collector.error_count(), 1);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ForEachField<L> {
+    lint: L,
+    skip_unnamed: bool,
+    skip_unions: bool,
+}
+
+impl<L> ForEachField<L> {
+    /// run the lint on all fields
+    pub fn new(lint: L) -> Self {
+        ForEachField {
+            lint,
+            skip_unnamed: false,
+            skip_unions: false,
+        }
+    }
+
+    /// don't run the lint on unnamed fields
+    pub fn skip_unnamed(mut self) -> Self {
+        self.skip_unnamed = true;
+        self
+    }
+
+    /// don't run the lint on the fields of unions
+    pub fn skip_unions(mut self) -> Self {
+        self.skip_unions = true;
+        self
+    }
+}
+
+impl<L: Lint<Field>> Lint<DeriveInput> for ForEachField<L> {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        let fields: Vec<&Field> = match &input.data {
+            Data::Struct(s) => s.fields.iter().collect(),
+            Data::Enum(e) => e.variants.iter().flat_map(|v| &v.fields).collect(),
+            Data::Union(_) if self.skip_unions => return,
+            Data::Union(u) => u.fields.named.iter().collect(),
+        };
+        for field in fields {
+            if self.skip_unnamed && field.ident.is_none() {
+                continue;
+            }
+            self.lint.lint(field, c);
+        }
+    }
+}
+
+/// run a `Lint<Variant>` on every variant of an enum
+///
+/// by default other inputs are rejected with the error of [`EnsureEnum`], use [`ForEachVariant::skip_non_enums`] to accept them
+///
+/// # Example
+/// ```
+/// use macro_compose::{lint_fn, Collector, Context, ForEachVariant};
+/// use syn::{parse_quote, DeriveInput, Expr, Variant};
+///
+/// let lint = ForEachVariant::new(lint_fn(|variant: &Variant, c| {
+///     if let Some((_, discriminant)) = &variant.discriminant {
+///         if !matches!(discriminant, Expr::Lit(_)) {
+///             c.error_spanned(discriminant, "expected a literal");
+///         }
+///     }
+/// }));
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(enum Foo { A = 1, B = 1 + 1 });
+/// Context::new(&mut collector, input).lint(&lint);
+/// assert_eq!(collector.error_count(), 1);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ForEachVariant<L> {
+    lint: L,
+    skip_non_enums: bool,
+}
+
+impl<L> ForEachVariant<L> {
+    /// run the lint on all variants and reject inputs that aren't enums
+    pub fn new(lint: L) -> Self {
+        ForEachVariant {
+            lint,
+            skip_non_enums: false,
+        }
+    }
+
+    /// accept inputs that aren't enums without reporting an error
+    pub fn skip_non_enums(mut self) -> Self {
+        self.skip_non_enums = true;
+        self
+    }
+}
+
+impl<L: Lint<Variant>> Lint<DeriveInput> for ForEachVariant<L> {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        match &input.data {
+            Data::Enum(e) => {
+                for variant in e.variants.iter() {
+                    self.lint.lint(variant, c);
+                }
+            }
+            _ if self.skip_non_enums => {}
+            _ => EnsureEnum::new().lint(input, c),
+        }
+    }
+}
@@ -7,12 +7,13 @@
 //!
 //! The examples are taken from the example macro in `examples/enum_from_str_macro` which implements a derive macro for `FromStr` for an enum.
 //! ## Linting and error handling
-//! The [`Lint`] trait is used to lint the macro input. [`Collector::error`] can be used to output errors.
+//! The [`Lint`] trait is used to lint the macro input. [`Collector::error`] can be used to output errors. [`Collector::warning`] can be used for diagnostics that shouldn't stop the expansion.
+//! The [`lints`] module contains ready-made lints for common checks.
 //! ### Example
 //! ```
 //! # extern crate proc_macro;
 //! use macro_compose::{Collector, Lint};
-//! use syn::{Data, DeriveInput, Error, Fields};
+//! use syn::{Data, DeriveInput, Fields};
 //!
 //! struct EnsureEnumLint;
 //!
@@ -22,11 +23,11 @@
 //!             Data::Enum(e) => {
 //!                 for variant in e.variants.iter() {
 //!                     if variant.fields != Fields::Unit {
-//!                         c.error(Error::new_spanned(&variant.fields, "unexpected fields"))
+//!                         c.error_spanned(&variant.fields, "unexpected fields")
 //!                     }
 //!                 }
 //!             }
-//!             _ => c.error(Error::new_spanned(input, "expected an enum")),
+//!             _ => c.error_spanned(input, "expected an enum"),
 //!         }
 //!     }
 //! }
@@ -43,14 +44,13 @@
 //! use macro_compose::{Collector, Expand};
 //! use proc_macro2::Ident;
 //! use syn::{parse_quote, Arm, Data, DeriveInput, Error, Fields, ItemImpl};
-//! # fn error_struct_ident(_: &DeriveInput) -> Ident {todo!()}
 //!
 //! struct ImplFromStrExpand;
 //!
-//! impl Expand<DeriveInput> for ImplFromStrExpand {
+//! impl Expand<(&DeriveInput, &Ident)> for ImplFromStrExpand {
 //!     type Output = ItemImpl;
 //!
-//!     fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
+//!     fn expand(&self, (input, error): &(&DeriveInput, &Ident), _: &mut Collector) -> Option<Self::Output> {
 //!         let variants = match &input.data {
 //!             Data::Enum(e) => &e.variants,
 //!             _ => unreachable!(),
@@ -66,7 +66,6 @@
 //!         });
 //!
 //!         let ident = &input.ident;
-//!         let error = error_struct_ident(input);
 //!         Some(parse_quote!(
 //!             impl ::core::str::FromStr for #ident {
 //!                 type Err = #error;
@@ -83,65 +82,215 @@
 //! }
 //! ```
 //! ## Implementing the macro
-//! [`Context::new_parse`] can be used to create a context from a [`TokenStream`](proc_macro::TokenStream).
+//! [`OwnedContext::new_parse`] can be used to create a context from a [`TokenStream`](proc_macro::TokenStream).
 //! This Context can be used to run `Lint`s and `Expand`s and get the resulting output.
+//! [`OwnedContext::capture_into`] and [`WithCaptured`] can be used to share values computed by one `Expand` with later ones.
+//! [`Analyze`] and [`OwnedContext::analyze_into`] can be used to compute a model of the input once and share it with later `Expand`s.
+//! [`pipeline!`] can be used to run several `Lint`s and `Expand`s in order.
 //! ### Example
 //! ```
 //! # extern crate proc_macro;
-//! use macro_compose::{Collector, Context};
+//! use macro_compose::{Collector, OwnedContext, WithCaptured};
 //! use proc_macro::TokenStream;
 //!
-//! # #[doc = "
+//! # #[doc = r##"
 //! #[proc_macro_derive(FromStr)]
 //! pub fn derive_from_str(item: TokenStream) -> TokenStream {
-//!     let mut collector = Collector::new();
+//!     let mut ctx = OwnedContext::new_parse(Collector::with_name("#[derive(FromStr)]"), item);
+//!     ctx.lint(&EnsureEnumLint);
+//!
+//!     ctx.capture_into::<ErrorStructIdent, _>(&ErrorStructIdentExpand);
+//!     ctx.expand(&WithCaptured::<ErrorStructIdent, _>::new(ErrorStructExpand));
+//!     ctx.expand(&WithCaptured::<ErrorStructIdent, _>::new(ImplDebugErrorStructExpand));
+//!     ctx.expand(&WithCaptured::<ErrorStructIdent, _>::new(ImplFromStrExpand));
+//!
+//!     ctx.finish().into()
+//! }
+//! # "##]
+//! # struct Foo;
+//! ```
+//!
+//! A [`Context`] borrows its [`Collector`] instead, this allows several contexts to report to the same collector.
+//! ### Example
+//! ```
+//! # extern crate proc_macro;
+//! use macro_compose::{Collector, Context, WithCaptured};
+//! use proc_macro::TokenStream;
+//!
+//! # #[doc = r##"
+//! #[proc_macro_derive(FromStr)]
+//! pub fn derive_from_str(item: TokenStream) -> TokenStream {
+//!     let mut collector = Collector::with_name("#[derive(FromStr)]");
 //!
 //!     let mut ctx = Context::new_parse(&mut collector, item);
 //!     ctx.lint(&EnsureEnumLint);
 //!
-//!     ctx.expand(&ErrorStructExpand);
-//!     ctx.expand(&ImplDebugErrorStructExpand);
-//!     ctx.expand(&ImplFromStrExpand);
+//!     ctx.capture_into::<ErrorStructIdent, _>(&ErrorStructIdentExpand);
+//!     ctx.expand(&WithCaptured::<ErrorStructIdent, _>::new(ErrorStructExpand));
+//!     ctx.expand(&WithCaptured::<ErrorStructIdent, _>::new(ImplDebugErrorStructExpand));
+//!     ctx.expand(&WithCaptured::<ErrorStructIdent, _>::new(ImplFromStrExpand));
 //!
-//!     collector.finish()
+//!     collector.finish().into()
 //! }
-//! # "]
+//! # "##]
 //! # struct Foo;
 //! ```
+//!
+//! # Features
+//! - `nightly-diagnostics`: emit errors and warnings through the unstable [`proc_macro::Diagnostic`] api instead of `compile_error!`s. this requires a nightly compiler
+//! - `timings`: record how long every `Lint` and `Expand` took, see `Collector::timings`. if the `MACRO_COMPOSE_TIMINGS` environment variable is set a summary is printed to stderr
+//! - `full`: enable the `full` feature of syn and the parts of the api that need it, eg. `Collector::set_verify_output` and `lints::FnSignatureLint`
 
 #![deny(missing_docs, clippy::doc_markdown)]
+#![cfg_attr(feature = "nightly-diagnostics", feature(proc_macro_diagnostic))]
 
 extern crate proc_macro;
 
+mod capture;
+mod cfg;
 mod context;
+mod diagnostic;
+mod each;
+mod lint_ext;
+pub mod lints;
+mod owned;
+mod pipeline;
+mod scope;
+mod shared;
+mod tuple;
 
-pub use context::{Collector, Context};
+pub use capture::{CaptureKey, WithCaptured};
+pub use cfg::{cfg_attrs, with_cfg, CfgAware};
+pub use context::{CaptureResult, Checkpoint, Collector, Context, ErrorEntry, Slot};
+pub use each::{ForEachField, ForEachVariant};
+pub use lint_ext::{
+    only_if, unless, And, ExpectError, Focused, Leveled, LintExt, OnlyIf, Or, Severity, TryFocused,
+};
+pub use owned::OwnedContext;
+pub use scope::{report, try_report};
+pub use shared::SharedCollector;
 
 use proc_macro2::TokenStream;
 use quote::ToTokens;
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    rc::Rc,
+    sync::Arc,
+};
 
 /// Lint is used for linting the macro input
 ///
 /// # Example
 /// ```
 /// use macro_compose::{Collector, Lint};
-/// use syn::{Data, DeriveInput, Error};
+/// use syn::{Data, DeriveInput};
 ///
 /// struct EnsureStructLint;
 ///
 /// impl Lint<DeriveInput> for EnsureStructLint {
 ///     fn lint(&self, input: &DeriveInput, c: &mut Collector) {
 ///         if !matches!(&input.data, Data::Struct(_)) {
-///             c.error(Error::new_spanned(input, "expected a struct"));
+///             c.error_spanned(input, "expected a struct");
 ///         }
 ///     }
 /// }
 /// ```
+///
+/// closures taking the input and the collector can be used as `Lint`s with [`lint_fn`]
+/// ```
+/// use macro_compose::{lint_fn, Collector, Context};
+/// use syn::{parse_quote, Data, DeriveInput};
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(enum Foo {});
+/// let passed = Context::new(&mut collector, input).lint(&lint_fn(|input: &DeriveInput, c| {
+///     if !matches!(&input.data, Data::Struct(_)) {
+///         c.error_spanned(input, "expected a struct");
+///     }
+/// }));
+/// assert!(!passed);
+/// ```
+///
+/// tuples of up to 8 `Lint`s and slices and `Vec`s of boxed lints (`Vec<Box<dyn Lint<I>>>`) implement `Lint` by running all of them in order.
+/// references to `Lint`s and `Box`es, `Rc`s and `Arc`s of `Lint`s implement `Lint` as well, including trait objects like `Arc<dyn Lint<I> + Send + Sync>`
 pub trait Lint<I> {
     /// lint the macro input
     fn lint(&self, input: &I, c: &mut Collector);
 }
 
+impl<I, L: Lint<I> + ?Sized> Lint<I> for &L {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        (**self).lint(input, c)
+    }
+}
+
+impl<I, L: Lint<I> + ?Sized> Lint<I> for Box<L> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        (**self).lint(input, c)
+    }
+}
+
+/// use a closure taking the input and the collector as a lint
+///
+/// closures don't implement `Lint` directly: a blanket impl for closures would overlap with the impls for `&L` and `Box<L>`,
+/// because references to and boxes of closures are closures too.
+/// the type of the input usually can't be inferred, so it has to be written out in the parameters of the closure or as the first type argument
+///
+/// # Example
+/// ```
+/// use macro_compose::{lint_fn, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = lint_fn(|input: &DeriveInput, c| {
+///     if input.ident.to_string().starts_with('_') {
+///         c.error_spanned(&input.ident, "names shouldn't start with an underscore");
+///     }
+/// });
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(struct _Foo;);
+/// assert!(!Context::new(&mut collector, input).lint(&lint));
+/// ```
+pub fn lint_fn<I, F: Fn(&I, &mut Collector)>(f: F) -> FnLint<F> {
+    FnLint(f)
+}
+
+/// a lint that runs a closure, see [`lint_fn`]
+#[derive(Clone, Copy, Debug)]
+pub struct FnLint<F>(pub F);
+
+impl<I, F: Fn(&I, &mut Collector)> Lint<I> for FnLint<F> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        (self.0)(input, c)
+    }
+}
+
+impl<I, L: Lint<I> + ?Sized> Lint<I> for Rc<L> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        (**self).lint(input, c)
+    }
+}
+
+impl<I, L: Lint<I> + ?Sized> Lint<I> for Arc<L> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        (**self).lint(input, c)
+    }
+}
+
+impl<I> Lint<I> for [Box<dyn Lint<I> + '_>] {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        for lint in self {
+            lint.lint(input, c);
+        }
+    }
+}
+
+impl<I> Lint<I> for Vec<Box<dyn Lint<I> + '_>> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        self.as_slice().lint(input, c)
+    }
+}
+
 /// Expand is used for expanding macros
 ///
 /// # Example
@@ -165,6 +314,9 @@ pub trait Lint<I> {
 ///     }
 /// }
 /// ```
+///
+/// tuples of up to 8 `Expand`s implement `Expand` by concatenating the outputs of their elements.
+/// once an element reports an error the remaining elements are skipped and the tuple doesn't produce an output
 pub trait Expand<I> {
     /// the output generated by the expansion
     type Output: ToTokens;
@@ -173,6 +325,318 @@ pub trait Expand<I> {
     fn expand(&self, input: &I, c: &mut Collector) -> Option<Self::Output>;
 }
 
+impl<I, E: Expand<I> + ?Sized> Expand<I> for &E {
+    type Output = E::Output;
+
+    fn expand(&self, input: &I, c: &mut Collector) -> Option<Self::Output> {
+        (**self).expand(input, c)
+    }
+}
+
+impl<I, E: Expand<I> + ?Sized> Expand<I> for Box<E> {
+    type Output = E::Output;
+
+    fn expand(&self, input: &I, c: &mut Collector) -> Option<Self::Output> {
+        (**self).expand(input, c)
+    }
+}
+
+impl<I, E: Expand<I> + ?Sized> Expand<I> for Rc<E> {
+    type Output = E::Output;
+
+    fn expand(&self, input: &I, c: &mut Collector) -> Option<Self::Output> {
+        (**self).expand(input, c)
+    }
+}
+
+impl<I, E: Expand<I> + ?Sized> Expand<I> for Arc<E> {
+    type Output = E::Output;
+
+    fn expand(&self, input: &I, c: &mut Collector) -> Option<Self::Output> {
+        (**self).expand(input, c)
+    }
+}
+
+/// `Analyze` is used to compute an intermediate model of the macro input that is shared by later passes
+///
+/// unlike `Expand`s analyses also run after errors have been reported, so errors about the input are reported together with the errors of `Lint`s.
+/// the output can be stored with [`Context::analyze_into`] and used by later `Expand`s with [`WithCaptured`] or [`Collector::captured`]
+///
+/// # Example
+/// ```
+/// use macro_compose::{Analyze, Collector, Context};
+/// use syn::{parse_quote, Data, DeriveInput, Ident};
+///
+/// struct VariantIdentsAnalyze;
+///
+/// impl Analyze<DeriveInput> for VariantIdentsAnalyze {
+///     type Output = Vec<Ident>;
+///
+///     fn analyze(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
+///         match &input.data {
+///             Data::Enum(e) => Some(e.variants.iter().map(|v| v.ident.clone()).collect()),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(enum Foo { Bar, Baz });
+/// let idents = Context::new(&mut collector, input).analyze(&VariantIdentsAnalyze).unwrap();
+/// assert_eq!(idents, ["Bar", "Baz"]);
+/// ```
+pub trait Analyze<I> {
+    /// the result of the analysis
+    type Output;
+
+    /// analyze the macro input
+    fn analyze(&self, input: &I, c: &mut Collector) -> Option<Self::Output>;
+}
+
+impl<I, A: Analyze<I> + ?Sized> Analyze<I> for &A {
+    type Output = A::Output;
+
+    fn analyze(&self, input: &I, c: &mut Collector) -> Option<Self::Output> {
+        (**self).analyze(input, c)
+    }
+}
+
+/// an object safe version of [`Expand`] that returns the output as a [`TokenStream`]
+///
+/// this is implemented for all `Expand`s and used to pass several `Expand`s with different outputs to [`Context::expand_all`]
+pub trait ErasedExpand<I> {
+    /// expand the macro and convert the output into tokens
+    fn expand_erased(&self, input: &I, c: &mut Collector) -> Option<TokenStream>;
+
+    /// the type name of the `Expand`, used for recording timings
+    #[cfg(feature = "timings")]
+    #[doc(hidden)]
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<I, E: Expand<I>> ErasedExpand<I> for E {
+    fn expand_erased(&self, input: &I, c: &mut Collector) -> Option<TokenStream> {
+        self.expand(input, c)
+            .map(|output| output.into_token_stream())
+    }
+}
+
+/// a step of a macro pipeline that can be either a lint or an expansion
+///
+/// `Lint`s and `Expand`s can be turned into passes with [`LintPass`] and [`ExpandPass`].
+/// this allows storing both in one collection and running them in order with [`Context::run_all`]
+///
+/// # Example
+/// ```
+/// use macro_compose::{Collector, Context, EchoExpand, ExpandPass, Lint, LintPass, Pass};
+/// use syn::{parse_quote, ItemConst};
+///
+/// struct NoOpLint;
+///
+/// impl Lint<ItemConst> for NoOpLint {
+///     fn lint(&self, _: &ItemConst, _: &mut Collector) {}
+/// }
+///
+/// let passes: Vec<Box<dyn Pass<ItemConst>>> = vec![
+///     Box::new(LintPass(NoOpLint)),
+///     Box::new(ExpandPass(EchoExpand)),
+/// ];
+///
+/// let mut collector = Collector::new();
+/// let input: ItemConst = parse_quote!(const FOO: u8 = 0;);
+/// let mut ctx = Context::new(&mut collector, input);
+/// for pass in passes.iter() {
+///     ctx.run(pass.as_ref());
+/// }
+/// assert_eq!(collector.finish().to_string(), "const FOO : u8 = 0 ;");
+/// ```
+pub trait Pass<I> {
+    /// run the pass and return the generated tokens
+    fn run(&self, input: &I, c: &mut Collector) -> Option<TokenStream>;
+
+    /// the type name of the pass, used for recording timings
+    #[cfg(feature = "timings")]
+    #[doc(hidden)]
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// run a `Lint` as a [`Pass`]
+///
+/// the lint never generates any tokens. like with [`Context::lint`] it always runs unless lint gating is enabled, see [`Collector::set_lint_gating`]
+pub struct LintPass<L>(pub L);
+
+impl<I, L: Lint<I>> Pass<I> for LintPass<L> {
+    fn run(&self, input: &I, c: &mut Collector) -> Option<TokenStream> {
+        if !c.skips_lints() {
+            self.0.lint(input, c);
+        }
+        None
+    }
+}
+
+/// run an `Expand` as a [`Pass`]
+///
+/// like with [`Context::expand`] the expansion is skipped once an error has been reported
+pub struct ExpandPass<E>(pub E);
+
+impl<I, E: Expand<I>> Pass<I> for ExpandPass<E> {
+    fn run(&self, input: &I, c: &mut Collector) -> Option<TokenStream> {
+        if c.has_new_errors() {
+            return None;
+        }
+        self.0.expand_erased(input, c)
+    }
+}
+
+/// a boxed `Expand` whose output is erased to a [`TokenStream`]
+///
+/// this can be used to store `Expand`s with different outputs together
+///
+/// # Example
+/// ```
+/// use macro_compose::{BoxedExpand, Collector, Context, EchoExpand, Expand};
+/// use syn::{parse_quote, Ident, ItemConst};
+///
+/// struct NameExpand;
+///
+/// impl Expand<ItemConst> for NameExpand {
+///     type Output = Ident;
+///
+///     fn expand(&self, input: &ItemConst, _: &mut Collector) -> Option<Self::Output> {
+///         Some(input.ident.clone())
+///     }
+/// }
+///
+/// let expands = vec![BoxedExpand::new(EchoExpand), BoxedExpand::new(NameExpand)];
+///
+/// let mut collector = Collector::new();
+/// let input: ItemConst = parse_quote!(const FOO: u8 = 0;);
+/// let mut ctx = Context::new(&mut collector, input);
+/// for expand in expands.iter() {
+///     ctx.expand(expand);
+/// }
+/// assert_eq!(collector.finish().to_string(), "const FOO : u8 = 0 ; FOO");
+/// ```
+pub struct BoxedExpand<'a, I>(Box<dyn ErasedExpand<I> + 'a>);
+
+impl<'a, I> BoxedExpand<'a, I> {
+    /// box an `Expand`
+    pub fn new(expand: impl Expand<I> + 'a) -> Self {
+        BoxedExpand(Box::new(expand))
+    }
+}
+
+impl<I> Expand<I> for BoxedExpand<'_, I> {
+    type Output = TokenStream;
+
+    fn expand(&self, input: &I, c: &mut Collector) -> Option<Self::Output> {
+        self.0.expand_erased(input, c)
+    }
+}
+
+/// `ExpandIter` is used for expanding macros into multiple items
+///
+/// unlike an `Expand` returning a [`TokenStream`], the items keep their types, see [`Context::expand_iter`]
+///
+/// # Example
+/// ```
+/// use macro_compose::{Collector, ExpandIter};
+/// use syn::{parse_quote, Data, DeriveInput, ItemImpl};
+///
+/// struct ImplMarkerPerVariantExpand;
+///
+/// impl ExpandIter<DeriveInput> for ImplMarkerPerVariantExpand {
+///     type Item = ItemImpl;
+///
+///     fn expand_iter(&self, input: &DeriveInput, _: &mut Collector) -> Option<Vec<Self::Item>> {
+///         let variants = match &input.data {
+///             Data::Enum(e) => &e.variants,
+///             _ => return None,
+///         };
+///         let ident = &input.ident;
+///         let impls = variants.iter().map(|v| {
+///             let v = &v.ident;
+///             parse_quote!(impl Marker<#v> for #ident {})
+///         });
+///         Some(impls.collect())
+///     }
+/// }
+/// ```
+pub trait ExpandIter<I> {
+    /// the type of the generated items
+    type Item: ToTokens;
+
+    /// expand the macro
+    fn expand_iter(&self, input: &I, c: &mut Collector) -> Option<Vec<Self::Item>>;
+}
+
+/// `LintMut` is used for linting the macro input with lints that keep state between invocations
+///
+/// this is implemented for all `Lint`s, see [`Context::lint_mut`]
+///
+/// # Example
+/// ```
+/// use macro_compose::{Collector, Context, LintMut};
+/// use syn::{parse_quote, DeriveInput, Variant};
+///
+/// struct MaxVariantsLint {
+///     seen: usize,
+///     max: usize,
+/// }
+///
+/// impl LintMut<Variant> for MaxVariantsLint {
+///     fn lint_mut(&mut self, input: &Variant, c: &mut Collector) {
+///         self.seen += 1;
+///         if self.seen > self.max {
+///             c.error_spanned(input, "too many variants");
+///         }
+///     }
+/// }
+///
+/// let input: DeriveInput = parse_quote!(enum Foo { A, B, C });
+///
+/// let mut lint = MaxVariantsLint { seen: 0, max: 2 };
+/// let mut collector = Collector::new();
+/// let mut ctx = Context::new(&mut collector, input);
+/// ctx.for_each_variant(|ctx| {
+///     ctx.lint_mut(&mut lint);
+/// });
+/// assert_eq!(collector.error_count(), 1);
+/// ```
+pub trait LintMut<I> {
+    /// lint the macro input
+    fn lint_mut(&mut self, input: &I, c: &mut Collector);
+}
+
+impl<I, L: Lint<I> + ?Sized> LintMut<I> for L {
+    fn lint_mut(&mut self, input: &I, c: &mut Collector) {
+        self.lint(input, c)
+    }
+}
+
+/// `ExpandMut` is used for expanding macros with `Expand`s that keep state between invocations
+///
+/// this is implemented for all `Expand`s, see [`Context::expand_mut`]
+pub trait ExpandMut<I> {
+    /// the output generated by the expansion
+    type Output: ToTokens;
+
+    /// expand the macro
+    fn expand_mut(&mut self, input: &I, c: &mut Collector) -> Option<Self::Output>;
+}
+
+impl<I, E: Expand<I> + ?Sized> ExpandMut<I> for E {
+    type Output = E::Output;
+
+    fn expand_mut(&mut self, input: &I, c: &mut Collector) -> Option<Self::Output> {
+        self.expand(input, c)
+    }
+}
+
 /// a helper struct for expanding to nothing
 pub struct Nothing;
 
@@ -190,3 +654,53 @@ impl<T: ToTokens + Clone> Expand<T> for EchoExpand {
         Some(input.clone())
     }
 }
+
+/// catch panics in an `Expand` and report them as errors
+///
+/// a panic is reported as an error spanned on the whole input, the expansion then returns `None`.
+/// the panic hook still runs, so the panic message is printed to stderr as well
+///
+/// # Example
+/// ```
+/// use macro_compose::{CatchPanic, Collector, Context, Expand, Nothing};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// struct PanickingExpand;
+///
+/// impl Expand<DeriveInput> for PanickingExpand {
+///     type Output = Nothing;
+///
+///     fn expand(&self, _: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
+///         panic!("oops")
+///     }
+/// }
+///
+/// let input: DeriveInput = parse_quote!(struct Foo;);
+/// let mut collector = Collector::new();
+/// let mut ctx = Context::new(&mut collector, input);
+/// ctx.expand(&CatchPanic(PanickingExpand));
+/// assert!(collector.has_errors());
+/// ```
+pub struct CatchPanic<E>(pub E);
+
+impl<T: ToTokens, E: Expand<T>> Expand<T> for CatchPanic<E> {
+    type Output = E::Output;
+
+    fn expand(&self, input: &T, c: &mut Collector) -> Option<Self::Output> {
+        let res = catch_unwind(AssertUnwindSafe(|| self.0.expand(input, c)));
+        match res {
+            Ok(output) => output,
+            Err(payload) => {
+                let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                    format!("expansion panicked: {}", message)
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    format!("expansion panicked: {}", message)
+                } else {
+                    "expansion panicked".to_string()
+                };
+                c.error_spanned(input, message);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use proc_macro2::Span;
+use syn::Error;
+
+use crate::{Collector, Lint};
+
+/// combinators for `Lint`s
+///
+/// this is implemented for every `Lint`, the combinators implement `Lint` if the combined lints do
+///
+/// # Example
+/// ```
+/// use macro_compose::{Collector, Context, Lint, LintExt};
+/// use syn::{parse_quote, Data, DeriveInput};
+///
+/// struct EnsureEnumLint;
+///
+/// impl Lint<DeriveInput> for EnsureEnumLint {
+///     fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+///         if !matches!(&input.data, Data::Enum(_)) {
+///             c.error_spanned(input, "expected an enum");
+///         }
+///     }
+/// }
+///
+/// struct NoGenericsLint;
+///
+/// impl Lint<DeriveInput> for NoGenericsLint {
+///     fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+///         if !input.generics.params.is_empty() {
+///             c.error_spanned(&input.generics, "generics are not supported");
+///         }
+///     }
+/// }
+///
+/// let lint = EnsureEnumLint.and(NoGenericsLint);
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(struct Foo<T>(T););
+/// assert!(!Context::new(&mut collector, input).lint(&lint));
+/// assert_eq!(collector.error_count(), 2);
+/// ```
+pub trait LintExt<I>: Lint<I> + Sized {
+    /// combine two lints into one that runs both
+    ///
+    /// the second lint runs even if the first one reports an error, so all diagnostics are reported
+    fn and<R: Lint<I>>(self, other: R) -> And<Self, R> {
+        And(self, other)
+    }
+
+    /// combine two lints into one that passes if either of them passes
+    ///
+    /// the lints run against temporary collectors with the same options and captured values. if the first lint passes the second one doesn't run.
+    /// the diagnostics of the passing lint are reported, if both lints fail the errors of both lints are reported
+    fn or<R: Lint<I>>(self, other: R) -> Or<Self, R> {
+        Or(self, other)
+    }
+
+    /// run the lint on a part of the input
+    ///
+    /// this turns a `Lint<I>` into a `Lint<J>`, eg. a `Lint<Generics>` into a `Lint<DeriveInput>`
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{Collector, Context, Lint, LintExt};
+    /// use syn::{parse_quote, DeriveInput, Generics};
+    ///
+    /// struct NoGenericsLint;
+    ///
+    /// impl Lint<Generics> for NoGenericsLint {
+    ///     fn lint(&self, input: &Generics, c: &mut Collector) {
+    ///         if !input.params.is_empty() {
+    ///             c.error_spanned(input, "generics are not supported");
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let lint = NoGenericsLint.focus(|input: &DeriveInput| &input.generics);
+    ///
+    /// let mut collector = Collector::new();
+    /// let input: DeriveInput = parse_quote!(struct Foo<T>(T););
+    /// assert!(!Context::new(&mut collector, input).lint(&lint));
+    /// ```
+    fn focus<J, F>(self, project: F) -> Focused<Self, F>
+    where
+        F: Fn(&J) -> &I,
+    {
+        Focused(self, project)
+    }
+
+    /// run the lint on a part of the input that might not exist
+    ///
+    /// nothing is reported if `project` returns `None`, use [`LintExt::try_focus_or`] to report an error instead
+    fn try_focus<J, F>(self, project: F) -> TryFocused<Self, F, fn(&J) -> Error>
+    where
+        F: Fn(&J) -> Option<&I>,
+    {
+        TryFocused {
+            lint: self,
+            project,
+            missing: None,
+        }
+    }
+
+    /// run the lint on a part of the input that might not exist and report the error returned by `missing` if it doesn't
+    fn try_focus_or<J, F, E>(self, project: F, missing: E) -> TryFocused<Self, F, E>
+    where
+        F: Fn(&J) -> Option<&I>,
+        E: Fn(&J) -> Error,
+    {
+        TryFocused {
+            lint: self,
+            project,
+            missing: Some(missing),
+        }
+    }
+
+    /// report the diagnostics of the lint with the severity
+    ///
+    /// with [`Severity::Warning`] the errors reported by the lint are turned into warnings, so they don't stop `Expand`s from running.
+    /// notes, help messages and codes of the errors are kept
+    ///
+    /// # Example
+    /// ```
+    /// use macro_compose::{lint_fn, Collector, Context, EchoExpand, LintExt, Severity};
+    /// use syn::{parse_quote, DeriveInput};
+    ///
+    /// let lint = lint_fn(|input: &DeriveInput, c| {
+    ///     if input.ident.to_string().starts_with('_') {
+    ///         c.error_spanned(&input.ident, "names shouldn't start with an underscore");
+    ///     }
+    /// })
+    /// .with_severity(Severity::Warning);
+    ///
+    /// let mut collector = Collector::new();
+    /// let input: DeriveInput = parse_quote!(struct _Foo;);
+    /// let mut ctx = Context::new(&mut collector, input);
+    /// assert!(ctx.lint(&lint));
+    /// ctx.expand(&EchoExpand);
+    /// assert!(!collector.has_errors());
+    /// ```
+    fn with_severity(self, severity: Severity) -> Leveled<Self> {
+        Leveled {
+            lint: self,
+            severity,
+        }
+    }
+}
+
+impl<I, L: Lint<I>> LintExt<I> for L {}
+
+/// a lint that runs two lints, see [`LintExt::and`]
+#[derive(Clone, Copy, Debug)]
+pub struct And<A, B>(A, B);
+
+impl<I, A: Lint<I>, B: Lint<I>> Lint<I> for And<A, B> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        self.0.lint(input, c);
+        self.1.lint(input, c);
+    }
+}
+
+/// a lint that passes if either of two lints passes, see [`LintExt::or`]
+#[derive(Clone, Copy, Debug)]
+pub struct Or<A, B>(A, B);
+
+impl<I, A: Lint<I>, B: Lint<I>> Lint<I> for Or<A, B> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        let mut first = c.scratch();
+        self.0.lint(input, &mut first);
+        if first.has_errors() {
+            let mut second = c.scratch();
+            self.1.lint(input, &mut second);
+            if second.has_errors() {
+                c.merge(first);
+            }
+            c.merge(second);
+        } else {
+            c.merge(first);
+        }
+    }
+}
+
+/// a lint that runs on a part of the input, see [`LintExt::focus`]
+#[derive(Clone, Copy, Debug)]
+pub struct Focused<L, F>(L, F);
+
+impl<I, J, L: Lint<I>, F: Fn(&J) -> &I> Lint<J> for Focused<L, F> {
+    fn lint(&self, input: &J, c: &mut Collector) {
+        self.0.lint((self.1)(input), c);
+    }
+}
+
+/// a lint that runs on a part of the input that might not exist, see [`LintExt::try_focus`]
+#[derive(Clone, Copy, Debug)]
+pub struct TryFocused<L, F, E> {
+    lint: L,
+    project: F,
+    missing: Option<E>,
+}
+
+impl<I, J, L, F, E> Lint<J> for TryFocused<L, F, E>
+where
+    L: Lint<I>,
+    F: Fn(&J) -> Option<&I>,
+    E: Fn(&J) -> Error,
+{
+    fn lint(&self, input: &J, c: &mut Collector) {
+        match (self.project)(input) {
+            Some(projected) => self.lint.lint(projected, c),
+            None => {
+                if let Some(missing) = &self.missing {
+                    c.error(missing(input));
+                }
+            }
+        }
+    }
+}
+
+/// the severity of the diagnostics of a lint, see [`LintExt::with_severity`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// report errors, which stop `Expand`s from running
+    Error,
+    /// report warnings, which don't stop `Expand`s from running
+    Warning,
+}
+
+/// a lint that reports its diagnostics with a severity, see [`LintExt::with_severity`]
+#[derive(Clone, Copy, Debug)]
+pub struct Leveled<L> {
+    lint: L,
+    severity: Severity,
+}
+
+impl<I, L: Lint<I>> Lint<I> for Leveled<L> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        match self.severity {
+            Severity::Error => self.lint.lint(input, c),
+            Severity::Warning => {
+                let mut scratch = c.scratch();
+                self.lint.lint(input, &mut scratch);
+                scratch.demote_errors();
+                c.merge(scratch);
+            }
+        }
+    }
+}
+
+/// run the lint only if the predicate returns true for the input
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::NoGenericsLint, only_if, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = only_if(
+///     |input: &DeriveInput| input.attrs.iter().any(|attr| attr.path.is_ident("simple")),
+///     NoGenericsLint,
+/// );
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(struct Foo<T>(T););
+/// assert!(Context::new(&mut collector, input).lint(&lint));
+///
+/// let input: DeriveInput = parse_quote!(#[simple] struct Foo<T>(T););
+/// assert!(!Context::new(&mut collector, input).lint(&lint));
+/// ```
+pub fn only_if<P, L>(predicate: P, lint: L) -> OnlyIf<P, L> {
+    OnlyIf {
+        predicate,
+        lint,
+        expected: true,
+    }
+}
+
+/// run the lint only if the predicate returns false for the input, see [`only_if`]
+pub fn unless<P, L>(predicate: P, lint: L) -> OnlyIf<P, L> {
+    OnlyIf {
+        predicate,
+        lint,
+        expected: false,
+    }
+}
+
+/// a lint that only runs depending on a predicate, see [`only_if`] and [`unless`]
+#[derive(Clone, Copy, Debug)]
+pub struct OnlyIf<P, L> {
+    predicate: P,
+    lint: L,
+    expected: bool,
+}
+
+impl<I, P: Fn(&I) -> bool, L: Lint<I>> Lint<I> for OnlyIf<P, L> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        if (self.predicate)(input) == self.expected {
+            self.lint.lint(input, c);
+        }
+    }
+}
+
+/// a lint that expects the inner lint to reject the input
+///
+/// the inner lint runs against a temporary collector with the same options and captured values. an error is reported if the inner lint passes or if none of its errors contain the expected message,
+/// otherwise nothing is reported. this is useful for testing lints
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::EnsureEnum, Collector, Context, ExpectError};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = ExpectError::new(EnsureEnum::new(), "expected an enum");
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(struct Foo;);
+/// assert!(Context::new(&mut collector, input).lint(&lint));
+///
+/// let input: DeriveInput = parse_quote!(enum Foo {});
+/// assert!(!Context::new(&mut collector, input).lint(&lint));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ExpectError<L> {
+    lint: L,
+    message: String,
+}
+
+impl<L> ExpectError<L> {
+    /// expect the lint to report an error containing `message`
+    pub fn new(lint: L, message: impl Into<String>) -> Self {
+        ExpectError {
+            lint,
+            message: message.into(),
+        }
+    }
+}
+
+impl<I, L: Lint<I>> Lint<I> for ExpectError<L> {
+    fn lint(&self, input: &I, c: &mut Collector) {
+        let mut scratch = c.scratch();
+        self.lint.lint(input, &mut scratch);
+
+        let messages: Vec<String> = scratch.errors().map(ToString::to_string).collect();
+        if messages.is_empty() {
+            let message = format!(
+                "expected the lint to report an error containing `{}`, but it passed",
+                self.message
+            );
+            c.error_at(Span::call_site(), message);
+        } else if !messages.iter().any(|m| m.contains(&self.message)) {
+            let found = messages
+                .iter()
+                .map(|m| format!("`{}`", m))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let message = format!(
+                "expected the lint to report an error containing `{}`, found {}",
+                self.message, found
+            );
+            c.error_at(Span::call_site(), message);
+        }
+    }
+}
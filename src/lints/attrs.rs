@@ -0,0 +1,122 @@
+use syn::{DeriveInput, Meta, NestedMeta};
+
+use crate::{Collector, Context, Lint};
+
+/// reject unknown keys in helper attributes
+///
+/// the item, variant and field attributes with the name are parsed as lists of keys, eg. `#[from_str(rename = "foo", skip)]`.
+/// an error spanned on the path of the key is reported for every key that isn't allowed, suggesting a similar key if there is one.
+/// attributes that can't be parsed are reported as malformed, see [`Context::<DeriveInput>::helper_attrs`]
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::AttributeAllowlistLint, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = AttributeAllowlistLint::new("from_str", &["rename", "default", "skip"]);
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(
+///     enum Foo {
+///         #[from_str(renme = "bar")]
+///         Bar,
+///     }
+/// );
+/// Context::new(&mut collector, input).lint(&lint);
+///
+/// let error = collector.errors().next().unwrap();
+/// assert_eq!(
+///     error.to_string(),
+///     "unknown key `renme` in `from_str` attribute, did you mean `rename`?"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct AttributeAllowlistLint {
+    name: String,
+    keys: Vec<String>,
+}
+
+impl AttributeAllowlistLint {
+    /// create a lint for the attributes with the name that allows the keys
+    ///
+    /// paths with multiple segments are written with `::`, eg. `"foo::bar"`
+    pub fn new(name: impl Into<String>, keys: &[&str]) -> Self {
+        AttributeAllowlistLint {
+            name: name.into(),
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+        }
+    }
+
+    fn lint_meta(&self, meta: &Meta, c: &mut Collector) {
+        let list = match meta {
+            Meta::Path(_) => return,
+            Meta::List(list) => list,
+            Meta::NameValue(_) => {
+                c.error_spanned(meta, format!("expected `#[{}(...)]`", self.name));
+                return;
+            }
+        };
+
+        for nested in list.nested.iter() {
+            let path = match nested {
+                NestedMeta::Meta(meta) => meta.path(),
+                NestedMeta::Lit(lit) => {
+                    c.error_spanned(lit, format!("expected a key in `{}` attribute", self.name));
+                    continue;
+                }
+            };
+            let key = match path.get_ident() {
+                Some(ident) => ident.to_string(),
+                None => {
+                    let message = format!("expected a key in `{}` attribute", self.name);
+                    c.error_spanned(path, message);
+                    continue;
+                }
+            };
+            if self.keys.contains(&key) {
+                continue;
+            }
+
+            let mut message = format!("unknown key `{}` in `{}` attribute", key, self.name);
+            if let Some(suggestion) = self.suggest(&key) {
+                message.push_str(&format!(", did you mean `{}`?", suggestion));
+            }
+            c.error_spanned(path, message);
+        }
+    }
+
+    /// the allowed key closest to `key` if it's similar enough
+    fn suggest(&self, key: &str) -> Option<&str> {
+        self.keys
+            .iter()
+            .map(|allowed| (edit_distance(key, allowed), allowed))
+            .filter(|(distance, allowed)| *distance <= 2 && *distance < allowed.len())
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, allowed)| allowed.as_str())
+    }
+}
+
+impl Lint<DeriveInput> for AttributeAllowlistLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        let metas = Context::new_by_ref(c, input).helper_attrs(&self.name);
+        for (_, meta) in metas {
+            self.lint_meta(&meta, c);
+        }
+    }
+}
+
+/// the levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == *cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
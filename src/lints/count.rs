@@ -0,0 +1,95 @@
+use syn::{Data, DeriveInput, Fields};
+
+use super::plural;
+use crate::{Collector, Lint};
+
+/// ensure an enum has at most the given number of variants
+///
+/// the error is spanned on the first variant beyond the limit. inputs that aren't enums are accepted
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::MaxVariantsLint, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(enum Foo { A, B, C });
+/// Context::new(&mut collector, input).lint(&MaxVariantsLint(2));
+///
+/// let error = collector.errors().next().unwrap();
+/// assert_eq!(error.to_string(), "expected at most 2 variants, found 3");
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct MaxVariantsLint(pub usize);
+
+impl Lint<DeriveInput> for MaxVariantsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if let Data::Enum(e) = &input.data {
+            if let Some(variant) = e.variants.iter().nth(self.0) {
+                let message = format!(
+                    "expected at most {} variant{}, found {}",
+                    self.0,
+                    plural(self.0),
+                    e.variants.len()
+                );
+                c.error_spanned(variant, message);
+            }
+        }
+    }
+}
+
+/// ensure an enum has at least the given number of variants
+///
+/// the error is spanned on the braces of the enum. inputs that aren't enums are accepted
+#[derive(Clone, Copy, Debug)]
+pub struct MinVariantsLint(pub usize);
+
+impl Lint<DeriveInput> for MinVariantsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if let Data::Enum(e) = &input.data {
+            if e.variants.len() < self.0 {
+                let message = format!(
+                    "expected at least {} variant{}, found {}",
+                    self.0,
+                    plural(self.0),
+                    e.variants.len()
+                );
+                c.error_at(e.brace_token.span, message);
+            }
+        }
+    }
+}
+
+/// ensure a struct, union or every variant of an enum has at most the given number of fields
+///
+/// the error is spanned on the first field beyond the limit
+#[derive(Clone, Copy, Debug)]
+pub struct MaxFieldsLint(pub usize);
+
+impl MaxFieldsLint {
+    fn lint_fields(&self, fields: &Fields, c: &mut Collector) {
+        if let Some(field) = fields.iter().nth(self.0) {
+            let message = format!(
+                "expected at most {} field{}, found {}",
+                self.0,
+                plural(self.0),
+                fields.len()
+            );
+            c.error_spanned(field, message);
+        }
+    }
+}
+
+impl Lint<DeriveInput> for MaxFieldsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        match &input.data {
+            Data::Struct(s) => self.lint_fields(&s.fields, c),
+            Data::Enum(e) => {
+                for variant in e.variants.iter() {
+                    self.lint_fields(&variant.fields, c);
+                }
+            }
+            Data::Union(u) => self.lint_fields(&Fields::Named(u.fields.clone()), c),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use proc_macro2::Span;
+use syn::{spanned::Spanned, Attribute, Data, DeriveInput, Fields, Lit, Meta};
+
+use crate::{Collector, Lint};
+
+/// require documentation on the item and optionally on its variants and fields
+///
+/// errors are spanned on the ident of the undocumented element or on the type of unnamed fields
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::RequireDocsLint, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = RequireDocsLint::new().variants();
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(
+///     /// a foo
+///     enum Foo {
+///         /// a bar
+///         Bar,
+///         Baz,
+///     }
+/// );
+/// Context::new(&mut collector, input).lint(&lint);
+///
+/// let error = collector.errors().next().unwrap();
+/// assert_eq!(error.to_string(), "missing documentation for `Baz`");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequireDocsLint {
+    variants: bool,
+    fields: bool,
+    deny_empty: bool,
+}
+
+impl RequireDocsLint {
+    /// create a lint that only requires documentation on the item
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// also require documentation on every variant of an enum
+    pub fn variants(mut self) -> Self {
+        self.variants = true;
+        self
+    }
+
+    /// also require documentation on every field of a struct, an enum variant or a union
+    pub fn fields(mut self) -> Self {
+        self.fields = true;
+        self
+    }
+
+    /// reject documentation that only contains whitespace
+    pub fn deny_empty(mut self) -> Self {
+        self.deny_empty = true;
+        self
+    }
+
+    fn lint_attrs(&self, attrs: &[Attribute], name: &str, span: Span, c: &mut Collector) {
+        let docs: Vec<(&Attribute, String)> = attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("doc"))
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(Meta::NameValue(nv)) => match nv.lit {
+                    Lit::Str(doc) => Some((attr, doc.value())),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        match docs.first() {
+            None => c.error_at(span, format!("missing documentation for `{}`", name)),
+            Some((attr, _))
+                if self.deny_empty && docs.iter().all(|(_, doc)| doc.trim().is_empty()) =>
+            {
+                c.error_spanned(attr, format!("empty documentation for `{}`", name))
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn lint_fields(&self, fields: &Fields, c: &mut Collector) {
+        for (i, field) in fields.iter().enumerate() {
+            match &field.ident {
+                Some(ident) => self.lint_attrs(&field.attrs, &ident.to_string(), ident.span(), c),
+                None => self.lint_attrs(&field.attrs, &i.to_string(), field.ty.span(), c),
+            }
+        }
+    }
+}
+
+impl Lint<DeriveInput> for RequireDocsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        self.lint_attrs(
+            &input.attrs,
+            &input.ident.to_string(),
+            input.ident.span(),
+            c,
+        );
+
+        match &input.data {
+            Data::Struct(s) if self.fields => self.lint_fields(&s.fields, c),
+            Data::Enum(e) => {
+                for variant in e.variants.iter() {
+                    if self.variants {
+                        let name = variant.ident.to_string();
+                        self.lint_attrs(&variant.attrs, &name, variant.ident.span(), c);
+                    }
+                    if self.fields {
+                        self.lint_fields(&variant.fields, c);
+                    }
+                }
+            }
+            Data::Union(u) if self.fields => self.lint_fields(&Fields::Named(u.fields.clone()), c),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use syn::{Data, DeriveInput, Fields, Ident};
+
+use super::{plural, shape::fields_span};
+use crate::{Collector, Lint};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Shape {
+    Any,
+    Unit,
+    Newtype,
+}
+
+/// restrict the fields of a struct or of every variant of an enum
+///
+/// errors are spanned on the offending fields or on the ident of unit structs and variants
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::FieldsShapeLint, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = FieldsShapeLint::newtype_only();
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(
+///     enum Foo {
+///         A(u8),
+///         B(u8, u8),
+///         C,
+///     }
+/// );
+/// Context::new(&mut collector, input).lint(&lint);
+/// assert_eq!(collector.error_count(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FieldsShapeLint {
+    shape: Shape,
+    max_fields: Option<usize>,
+    allow_named: bool,
+    message: Option<String>,
+}
+
+impl FieldsShapeLint {
+    /// create a lint that allows all fields
+    pub fn new() -> Self {
+        FieldsShapeLint {
+            shape: Shape::Any,
+            max_fields: None,
+            allow_named: true,
+            message: None,
+        }
+    }
+
+    /// only allow unit structs and variants
+    ///
+    /// the error message defaults to "unexpected fields"
+    pub fn unit_only() -> Self {
+        FieldsShapeLint {
+            shape: Shape::Unit,
+            ..Self::new()
+        }
+    }
+
+    /// only allow exactly one unnamed field
+    pub fn newtype_only() -> Self {
+        FieldsShapeLint {
+            shape: Shape::Newtype,
+            ..Self::new()
+        }
+    }
+
+    /// reject fields after the first `max` ones
+    pub fn max_fields(mut self, max: usize) -> Self {
+        self.max_fields = Some(max);
+        self
+    }
+
+    /// whether named fields are allowed, defaults to `true`
+    pub fn allow_named(mut self, allow: bool) -> Self {
+        self.allow_named = allow;
+        self
+    }
+
+    /// replace the message of the errors
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    fn lint_fields(&self, ident: &Ident, fields: &Fields, c: &mut Collector) {
+        let message = match self.shape {
+            Shape::Unit if *fields != Fields::Unit => Some("unexpected fields".to_string()),
+            Shape::Newtype if !matches!(fields, Fields::Unnamed(f) if f.unnamed.len() == 1) => {
+                Some("expected exactly one unnamed field".to_string())
+            }
+            _ if !self.allow_named && matches!(fields, Fields::Named(_)) => {
+                Some("named fields are not supported".to_string())
+            }
+            _ => match self.max_fields {
+                Some(max) if fields.len() > max => {
+                    Some(format!("expected at most {} field{}", max, plural(max)))
+                }
+                _ => None,
+            },
+        };
+
+        if let Some(message) = message {
+            let message = self.message.clone().unwrap_or(message);
+            c.error_at(fields_span(ident, fields), message);
+        }
+    }
+}
+
+impl Default for FieldsShapeLint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lint<DeriveInput> for FieldsShapeLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        match &input.data {
+            Data::Struct(s) => self.lint_fields(&input.ident, &s.fields, c),
+            Data::Enum(e) => {
+                for variant in e.variants.iter() {
+                    self.lint_fields(&variant.ident, &variant.fields, c);
+                }
+            }
+            Data::Union(u) => {
+                self.lint_fields(&input.ident, &Fields::Named(u.fields.clone()), c);
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use syn::{FnArg, ItemFn, Pat, ReturnType, Signature, Type};
+
+use crate::{Collector, Lint};
+
+/// the kind of a `self` parameter, see [`FnSignatureLint::require_receiver`]
+///
+/// typed receivers are supported as well, eg. `self: &Self` is a [`ReceiverKind::Ref`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReceiverKind {
+    /// `self` or `mut self`
+    Value,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+}
+
+impl ReceiverKind {
+    /// the kind of a `self` parameter, typed receivers other than `Self`, `&Self` and `&mut Self` (eg. `self: Box<Self>`) don't have one
+    fn of(receiver: &FnArg) -> Option<Self> {
+        match receiver {
+            FnArg::Receiver(receiver) => Some(match &receiver.reference {
+                None => ReceiverKind::Value,
+                Some(_) if receiver.mutability.is_some() => ReceiverKind::RefMut,
+                Some(_) => ReceiverKind::Ref,
+            }),
+            FnArg::Typed(arg) => match &*arg.ty {
+                ty if is_self(ty) => Some(ReceiverKind::Value),
+                Type::Reference(r) if is_self(&r.elem) && r.mutability.is_some() => {
+                    Some(ReceiverKind::RefMut)
+                }
+                Type::Reference(r) if is_self(&r.elem) => Some(ReceiverKind::Ref),
+                _ => None,
+            },
+        }
+    }
+
+    fn expected(self) -> &'static str {
+        match self {
+            ReceiverKind::Value => "expected a `self` parameter",
+            ReceiverKind::Ref => "expected a `&self` parameter",
+            ReceiverKind::RefMut => "expected a `&mut self` parameter",
+        }
+    }
+}
+
+fn is_self(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.qself.is_none() && p.path.is_ident("Self"))
+}
+
+/// the first parameter of the signature if it's a `self` parameter
+fn receiver(sig: &Signature) -> Option<&FnArg> {
+    sig.inputs.first().filter(|arg| match arg {
+        FnArg::Receiver(_) => true,
+        FnArg::Typed(arg) => matches!(&*arg.pat, Pat::Ident(pat) if pat.ident == "self"),
+    })
+}
+
+/// checks if the type is a `Result`, eg. `Result<T, E>` or `std::io::Result<T>`
+///
+/// only the last segment of the path is compared, so aliases named `Result` are accepted as well.
+/// this is meant to be used with [`FnSignatureLint::require_output_matches`]
+pub fn is_result(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Result"),
+        _ => false,
+    }
+}
+
+type OutputPredicate = Box<dyn Fn(&Type) -> bool>;
+
+/// validate the signature of a function
+///
+/// errors are spanned on the offending part of the signature, eg. the `async` keyword or the return type.
+/// this requires the `full` feature
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::FnSignatureLint, Collector, Context};
+/// use syn::{parse_quote, ItemFn, Type};
+///
+/// let lint = FnSignatureLint::new()
+///     .deny_async()
+///     .require_output_matches(|ty: &Type| matches!(ty, Type::Path(p) if p.path.is_ident("u8")));
+///
+/// let mut collector = Collector::new();
+/// let input: ItemFn = parse_quote!(async fn foo() -> u16 { 42 });
+/// Context::new(&mut collector, input).lint(&lint);
+/// assert_eq!(collector.error_count(), 2);
+/// ```
+#[derive(Default)]
+pub struct FnSignatureLint {
+    deny_async: bool,
+    deny_unsafe: bool,
+    receiver: Option<Option<ReceiverKind>>,
+    output: Option<OutputPredicate>,
+}
+
+impl FnSignatureLint {
+    /// create a lint that allows all signatures
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// reject `async` functions
+    pub fn deny_async(mut self) -> Self {
+        self.deny_async = true;
+        self
+    }
+
+    /// reject `unsafe` functions
+    pub fn deny_unsafe(mut self) -> Self {
+        self.deny_unsafe = true;
+        self
+    }
+
+    /// require a `self` parameter of the kind
+    pub fn require_receiver(mut self, kind: ReceiverKind) -> Self {
+        self.receiver = Some(Some(kind));
+        self
+    }
+
+    /// reject functions with a `self` parameter
+    pub fn deny_receiver(mut self) -> Self {
+        self.receiver = Some(None);
+        self
+    }
+
+    /// require a return type for which the predicate returns true
+    pub fn require_output_matches(mut self, predicate: impl Fn(&Type) -> bool + 'static) -> Self {
+        self.output = Some(Box::new(predicate));
+        self
+    }
+}
+
+impl Lint<Signature> for FnSignatureLint {
+    fn lint(&self, input: &Signature, c: &mut Collector) {
+        if self.deny_async {
+            if let Some(asyncness) = &input.asyncness {
+                c.error_spanned(asyncness, "async functions are not supported");
+            }
+        }
+
+        if self.deny_unsafe {
+            if let Some(unsafety) = &input.unsafety {
+                c.error_spanned(unsafety, "unsafe functions are not supported");
+            }
+        }
+
+        if let Some(expected) = self.receiver {
+            match (expected, receiver(input)) {
+                (Some(kind), Some(receiver)) if ReceiverKind::of(receiver) != Some(kind) => {
+                    c.error_spanned(receiver, kind.expected())
+                }
+                (Some(kind), None) => c.error_at(input.paren_token.span, kind.expected()),
+                (None, Some(receiver)) => {
+                    c.error_spanned(receiver, "`self` parameters are not supported")
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(predicate) = &self.output {
+            match &input.output {
+                ReturnType::Type(_, ty) if predicate(ty) => {}
+                ReturnType::Type(_, ty) => c.error_spanned(ty, "unexpected return type"),
+                ReturnType::Default => c.error_at(input.paren_token.span, "expected a return type"),
+            }
+        }
+    }
+}
+
+impl Lint<ItemFn> for FnSignatureLint {
+    fn lint(&self, input: &ItemFn, c: &mut Collector) {
+        self.lint(&input.sig, c);
+    }
+}
@@ -0,0 +1,221 @@
+use syn::{
+    DeriveInput, GenericParam, Generics, TraitBoundModifier, TypeParamBound, WherePredicate,
+};
+
+use crate::{Collector, Lint};
+
+/// ensure the input doesn't have any generic parameters
+///
+/// an error spanned on the parameter is reported for every generic parameter
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoGenericsLint;
+
+impl Lint<Generics> for NoGenericsLint {
+    fn lint(&self, input: &Generics, c: &mut Collector) {
+        for param in input.params.iter() {
+            c.error_spanned(param, "generic parameters are not supported");
+        }
+    }
+}
+
+impl Lint<DeriveInput> for NoGenericsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        self.lint(&input.generics, c);
+    }
+}
+
+/// restrict the kinds and number of generic parameters
+///
+/// errors are spanned on the offending parameters. by default all parameters are allowed
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::GenericsLint, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = GenericsLint::new().deny_lifetimes().max_type_params(1);
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(struct Foo<'a, A, B>(&'a A, B););
+/// Context::new(&mut collector, input).lint(&lint);
+/// assert_eq!(collector.error_count(), 2);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GenericsLint {
+    deny_lifetimes: bool,
+    deny_const_params: bool,
+    max_type_params: Option<usize>,
+}
+
+impl GenericsLint {
+    /// create a lint that allows all generic parameters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// reject lifetime parameters
+    pub fn deny_lifetimes(mut self) -> Self {
+        self.deny_lifetimes = true;
+        self
+    }
+
+    /// reject const parameters
+    pub fn deny_const_params(mut self) -> Self {
+        self.deny_const_params = true;
+        self
+    }
+
+    /// reject type parameters
+    pub fn deny_type_params(self) -> Self {
+        self.max_type_params(0)
+    }
+
+    /// reject all type parameters after the first `max` ones
+    pub fn max_type_params(mut self, max: usize) -> Self {
+        self.max_type_params = Some(max);
+        self
+    }
+}
+
+impl Lint<Generics> for GenericsLint {
+    fn lint(&self, input: &Generics, c: &mut Collector) {
+        let mut type_params = 0;
+        for param in input.params.iter() {
+            match param {
+                GenericParam::Lifetime(_) if self.deny_lifetimes => {
+                    c.error_spanned(param, "lifetime parameters are not supported")
+                }
+                GenericParam::Const(_) if self.deny_const_params => {
+                    c.error_spanned(param, "const parameters are not supported")
+                }
+                GenericParam::Type(_) => {
+                    type_params += 1;
+                    match self.max_type_params {
+                        Some(0) => c.error_spanned(param, "type parameters are not supported"),
+                        Some(max) if type_params > max => {
+                            let message = if max == 1 {
+                                "at most 1 type parameter is supported".to_string()
+                            } else {
+                                format!("at most {} type parameters are supported", max)
+                            };
+                            c.error_spanned(param, message);
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Lint<DeriveInput> for GenericsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        self.lint(&input.generics, c);
+    }
+}
+
+/// reject bounds on the generic parameters
+///
+/// bounds on type parameters and in the where clause are checked, errors are spanned on the offending bounds
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::BoundsLint, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = BoundsLint::new().deny_maybe_sized().deny_bound_on_param("Clone");
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(struct Foo<A: ?Sized, B>(Box<A>, B) where B: Clone;);
+/// Context::new(&mut collector, input).lint(&lint);
+/// assert_eq!(collector.error_count(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BoundsLint {
+    deny_maybe_sized: bool,
+    denied_traits: Vec<String>,
+    deny_where_clauses: bool,
+}
+
+impl BoundsLint {
+    /// create a lint that allows all bounds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// reject `?Sized` bounds
+    pub fn deny_maybe_sized(mut self) -> Self {
+        self.deny_maybe_sized = true;
+        self
+    }
+
+    /// reject bounds on the trait, eg. `"Clone"` for `T: Clone` or `T: std::clone::Clone`
+    ///
+    /// only the last segment of the path of the bound is compared
+    pub fn deny_bound_on_param(mut self, name: impl Into<String>) -> Self {
+        self.denied_traits.push(name.into());
+        self
+    }
+
+    /// reject non-empty where clauses
+    pub fn deny_where_clauses(mut self) -> Self {
+        self.deny_where_clauses = true;
+        self
+    }
+
+    fn lint_bound(&self, bound: &TypeParamBound, c: &mut Collector) {
+        let bound = match bound {
+            TypeParamBound::Trait(bound) => bound,
+            TypeParamBound::Lifetime(_) => return,
+        };
+
+        if let TraitBoundModifier::Maybe(_) = bound.modifier {
+            if self.deny_maybe_sized {
+                c.error_spanned(bound, "`?Sized` bounds are not supported");
+            }
+            return;
+        }
+
+        if let Some(segment) = bound.path.segments.last() {
+            if self.denied_traits.iter().any(|name| segment.ident == name) {
+                c.error_spanned(
+                    bound,
+                    format!("`{}` bounds are not supported", segment.ident),
+                );
+            }
+        }
+    }
+}
+
+impl Lint<Generics> for BoundsLint {
+    fn lint(&self, input: &Generics, c: &mut Collector) {
+        for param in input.type_params() {
+            for bound in param.bounds.iter() {
+                self.lint_bound(bound, c);
+            }
+        }
+
+        let where_clause = match &input.where_clause {
+            Some(where_clause) if !where_clause.predicates.is_empty() => where_clause,
+            _ => return,
+        };
+        if self.deny_where_clauses {
+            c.error_spanned(where_clause, "where clauses are not supported");
+            return;
+        }
+        for predicate in where_clause.predicates.iter() {
+            if let WherePredicate::Type(predicate) = predicate {
+                for bound in predicate.bounds.iter() {
+                    self.lint_bound(bound, c);
+                }
+            }
+        }
+    }
+}
+
+impl Lint<DeriveInput> for BoundsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        self.lint(&input.generics, c);
+    }
+}
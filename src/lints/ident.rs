@@ -0,0 +1,88 @@
+use syn::{DeriveInput, Ident};
+
+use crate::{Collector, Lint};
+
+/// reject reserved names for the derived type
+///
+/// by default names starting with `__` and raw keywords like `r#type` are rejected, more names can be reserved with [`ReservedIdentLint::deny`] and [`ReservedIdentLint::deny_prefix`].
+/// the error is spanned on the ident
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::ReservedIdentLint, Collector, Context};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let lint = ReservedIdentLint::new().deny("Error");
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(struct __Foo;);
+/// Context::new(&mut collector, input).lint(&lint);
+///
+/// let error = collector.errors().next().unwrap();
+/// assert_eq!(error.to_string(), "the name `__Foo` is reserved");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReservedIdentLint {
+    names: Vec<String>,
+    prefixes: Vec<String>,
+    message: Option<String>,
+}
+
+impl ReservedIdentLint {
+    /// create a lint that rejects names starting with `__` and raw keywords
+    pub fn new() -> Self {
+        ReservedIdentLint {
+            names: Vec::new(),
+            prefixes: vec!["__".to_string()],
+            message: None,
+        }
+    }
+
+    /// reserve the name
+    pub fn deny(mut self, name: impl Into<String>) -> Self {
+        self.names.push(name.into());
+        self
+    }
+
+    /// reserve all names starting with the prefix
+    pub fn deny_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// replace the message of the errors
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+impl Default for ReservedIdentLint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lint<Ident> for ReservedIdentLint {
+    fn lint(&self, input: &Ident, c: &mut Collector) {
+        let name = input.to_string();
+        let message = match name.strip_prefix("r#") {
+            Some(keyword) if syn::parse_str::<Ident>(keyword).is_err() => {
+                format!("the name `{}` is a keyword", keyword)
+            }
+            _ if self.names.contains(&name)
+                || self.prefixes.iter().any(|prefix| name.starts_with(prefix)) =>
+            {
+                format!("the name `{}` is reserved", name)
+            }
+            _ => return,
+        };
+        c.error_spanned(input, self.message.clone().unwrap_or(message));
+    }
+}
+
+impl Lint<DeriveInput> for ReservedIdentLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        self.lint(&input.ident, c);
+    }
+}
@@ -0,0 +1,45 @@
+//! ready-made `Lint`s for common checks
+//!
+//! # Example
+//! ```
+//! use macro_compose::{lints::{EnsureEnum, EnsureUnitVariants}, Collector, Context, LintExt};
+//! use syn::{parse_quote, DeriveInput};
+//!
+//! let mut collector = Collector::new();
+//! let input: DeriveInput = parse_quote!(enum Foo { Bar(u8) });
+//! let passed = Context::new(&mut collector, input).lint(&EnsureEnum::new().and(EnsureUnitVariants::new()));
+//! assert!(!passed);
+//! ```
+
+mod attrs;
+mod count;
+mod docs;
+mod fields;
+#[cfg(feature = "full")]
+mod function;
+mod generics;
+mod ident;
+mod shape;
+mod unique;
+
+pub use attrs::AttributeAllowlistLint;
+pub use count::{MaxFieldsLint, MaxVariantsLint, MinVariantsLint};
+pub use docs::RequireDocsLint;
+pub use fields::FieldsShapeLint;
+#[cfg(feature = "full")]
+pub use function::{is_result, FnSignatureLint, ReceiverKind};
+pub use generics::{BoundsLint, GenericsLint, NoGenericsLint};
+pub use ident::ReservedIdentLint;
+pub use shape::{
+    EnsureEnum, EnsureNamedFields, EnsureStruct, EnsureTupleStruct, EnsureUnion, EnsureUnitVariants,
+};
+pub use unique::UniqueNamesLint;
+
+/// the suffix for the plural of a word if there are `n` things
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
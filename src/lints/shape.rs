@@ -0,0 +1,160 @@
+use proc_macro2::Span;
+use syn::{Data, DeriveInput, Fields, Ident};
+
+use crate::{Collector, Lint};
+
+/// define a lint with a configurable message
+macro_rules! shape_lint {
+    ($(#[$attr:meta])* $name:ident, $message:literal) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug)]
+        pub struct $name {
+            message: Option<String>,
+        }
+
+        impl $name {
+            /// create the lint with the default message
+            pub fn new() -> Self {
+                $name { message: None }
+            }
+
+            /// replace the message of the errors
+            pub fn with_message(mut self, message: impl Into<String>) -> Self {
+                self.message = Some(message.into());
+                self
+            }
+
+            fn message(&self) -> &str {
+                self.message.as_deref().unwrap_or($message)
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    };
+}
+
+shape_lint!(
+    /// ensure the input is an enum
+    ///
+    /// the error is spanned on the `struct` or `union` token
+    EnsureEnum,
+    "expected an enum"
+);
+
+impl Lint<DeriveInput> for EnsureEnum {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if !matches!(input.data, Data::Enum(_)) {
+            c.error_at(data_token_span(input), self.message());
+        }
+    }
+}
+
+shape_lint!(
+    /// ensure the input is a struct
+    ///
+    /// the error is spanned on the `enum` or `union` token
+    EnsureStruct,
+    "expected a struct"
+);
+
+impl Lint<DeriveInput> for EnsureStruct {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if !matches!(input.data, Data::Struct(_)) {
+            c.error_at(data_token_span(input), self.message());
+        }
+    }
+}
+
+shape_lint!(
+    /// ensure the input is a union
+    ///
+    /// the error is spanned on the `struct` or `enum` token
+    EnsureUnion,
+    "expected a union"
+);
+
+impl Lint<DeriveInput> for EnsureUnion {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if !matches!(input.data, Data::Union(_)) {
+            c.error_at(data_token_span(input), self.message());
+        }
+    }
+}
+
+shape_lint!(
+    /// ensure the input is a struct with named fields
+    ///
+    /// the error is spanned on the fields of other structs and on the `enum` or `union` token of other inputs
+    EnsureNamedFields,
+    "expected a struct with named fields"
+);
+
+impl Lint<DeriveInput> for EnsureNamedFields {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        match &input.data {
+            Data::Struct(s) if matches!(s.fields, Fields::Named(_)) => {}
+            Data::Struct(s) => c.error_at(fields_span(&input.ident, &s.fields), self.message()),
+            _ => c.error_at(data_token_span(input), self.message()),
+        }
+    }
+}
+
+shape_lint!(
+    /// ensure the input is a tuple struct
+    ///
+    /// the error is spanned on the fields of other structs and on the `enum` or `union` token of other inputs
+    EnsureTupleStruct,
+    "expected a tuple struct"
+);
+
+impl Lint<DeriveInput> for EnsureTupleStruct {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        match &input.data {
+            Data::Struct(s) if matches!(s.fields, Fields::Unnamed(_)) => {}
+            Data::Struct(s) => c.error_at(fields_span(&input.ident, &s.fields), self.message()),
+            _ => c.error_at(data_token_span(input), self.message()),
+        }
+    }
+}
+
+shape_lint!(
+    /// ensure all variants of an enum are unit variants
+    ///
+    /// an error spanned on the fields is reported for every variant with fields. inputs that aren't enums are accepted, combine this with [`EnsureEnum`] to reject them
+    EnsureUnitVariants,
+    "expected a unit variant"
+);
+
+impl Lint<DeriveInput> for EnsureUnitVariants {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if let Data::Enum(e) = &input.data {
+            for variant in e.variants.iter() {
+                if variant.fields != Fields::Unit {
+                    c.error_spanned(&variant.fields, self.message());
+                }
+            }
+        }
+    }
+}
+
+/// the span of the `struct`, `enum` or `union` token
+fn data_token_span(input: &DeriveInput) -> Span {
+    match &input.data {
+        Data::Struct(s) => s.struct_token.span,
+        Data::Enum(e) => e.enum_token.span,
+        Data::Union(u) => u.union_token.span,
+    }
+}
+
+/// the span of the fields or the ident for unit fields
+pub(super) fn fields_span(ident: &Ident, fields: &Fields) -> Span {
+    match fields {
+        Fields::Named(fields) => fields.brace_token.span,
+        Fields::Unnamed(fields) => fields.paren_token.span,
+        Fields::Unit => ident.span(),
+    }
+}
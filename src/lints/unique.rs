@@ -0,0 +1,99 @@
+use syn::{Data, DeriveInput, Error, Ident, Variant};
+
+use crate::{Collector, Lint};
+
+/// ensure all variants of an enum map to different names
+///
+/// the name of every variant is computed with the closure, eg. the string a variant is parsed from after applying a `rename_all` option.
+/// an error spanned on the ident is reported for every variant whose name has already been used, with a note naming the first variant with the name.
+/// inputs that aren't enums are accepted
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::UniqueNamesLint, Collector, Context};
+/// use syn::{parse_quote, DeriveInput, Variant};
+///
+/// let lint = UniqueNamesLint::new(|variant: &Variant| variant.ident.to_string().to_lowercase());
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(enum Foo { Foo, Bar, FOO });
+/// Context::new(&mut collector, input).lint(&lint);
+///
+/// let error = collector.errors().next().unwrap();
+/// assert_eq!(error.to_string(), "the name `foo` is already used");
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct UniqueNamesLint<F> {
+    name: F,
+    ignore_case: bool,
+    trim: bool,
+}
+
+impl<F: Fn(&Variant) -> String> UniqueNamesLint<F> {
+    /// create a lint that compares the names returned by the closure
+    pub fn new(name: F) -> Self {
+        UniqueNamesLint {
+            name,
+            ignore_case: false,
+            trim: false,
+        }
+    }
+
+    /// compare the names case-insensitively
+    pub fn ignore_case(mut self) -> Self {
+        self.ignore_case = true;
+        self
+    }
+
+    /// ignore leading and trailing whitespace of the names
+    pub fn trim(mut self) -> Self {
+        self.trim = true;
+        self
+    }
+
+    fn normalized_name(&self, variant: &Variant) -> String {
+        let mut name = (self.name)(variant);
+        if self.trim {
+            name = name.trim().to_string();
+        }
+        if self.ignore_case {
+            name = name.to_lowercase();
+        }
+        name
+    }
+}
+
+impl UniqueNamesLint<fn(&Variant) -> String> {
+    /// create a lint that compares the idents of the variants case-insensitively
+    pub fn case_insensitive() -> Self {
+        Self::new(ident_name as fn(&Variant) -> String).ignore_case()
+    }
+}
+
+fn ident_name(variant: &Variant) -> String {
+    variant.ident.to_string()
+}
+
+impl<F: Fn(&Variant) -> String> Lint<DeriveInput> for UniqueNamesLint<F> {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        let variants = match &input.data {
+            Data::Enum(e) => &e.variants,
+            _ => return,
+        };
+
+        let mut seen: Vec<(String, &Ident)> = Vec::new();
+        for variant in variants.iter() {
+            let name = self.normalized_name(variant);
+            match seen.iter().find(|(other, _)| *other == name) {
+                Some((_, first)) => {
+                    let message = format!("the name `{}` is already used", name);
+                    c.error_with_note(
+                        Error::new_spanned(&variant.ident, message),
+                        format!("`{}` is first used by `{}`", name, first),
+                    );
+                }
+                None => seen.push((name, &variant.ident)),
+            }
+        }
+    }
+}
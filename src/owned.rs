@@ -0,0 +1,154 @@
+use proc_macro2::TokenStream;
+use syn::{parse, parse::Parse, parse2, Error};
+
+use crate::{Analyze, CaptureKey, Collector, Context, Expand, Lint};
+
+/// a context that owns its collector and its data
+///
+/// this is a shorthand for the common case of a macro that only uses a single context, see [`Context`] for the details.
+/// use [`OwnedContext::context`] to get a borrowed context for the other methods
+///
+/// # Example
+/// ```
+/// use macro_compose::{Collector, EchoExpand, Lint, OwnedContext};
+/// use syn::ItemConst;
+///
+/// struct NoOpLint;
+///
+/// impl Lint<ItemConst> for NoOpLint {
+///     fn lint(&self, _: &ItemConst, _: &mut Collector) {}
+/// }
+///
+/// let tokens = quote::quote!(const FOO: u8 = 0;);
+/// let mut ctx = OwnedContext::<ItemConst>::new_parse2(Collector::new(), tokens);
+/// ctx.lint(&NoOpLint);
+/// ctx.expand(&EchoExpand);
+/// assert_eq!(ctx.finish().to_string(), "const FOO : u8 = 0 ;");
+/// ```
+#[derive(Debug)]
+pub struct OwnedContext<T> {
+    collector: Collector,
+    data: Option<T>,
+    poisoned: bool,
+}
+
+impl<T> OwnedContext<T> {
+    /// create a new context with the collector and the data
+    pub fn new(collector: Collector, data: T) -> Self {
+        OwnedContext {
+            collector,
+            data: Some(data),
+            poisoned: false,
+        }
+    }
+
+    /// try to parse the data from a [`proc_macro::TokenStream`], see [`Context::new_parse`]
+    pub fn new_parse(collector: Collector, data: proc_macro::TokenStream) -> Self
+    where
+        T: Parse,
+    {
+        Self::from_result(collector, parse::<T>(data))
+    }
+
+    /// try to parse the data from a [`proc_macro2::TokenStream`], see [`Context::new_parse2`]
+    pub fn new_parse2(collector: Collector, data: TokenStream) -> Self
+    where
+        T: Parse,
+    {
+        Self::from_result(collector, parse2::<T>(data))
+    }
+
+    fn from_result(mut collector: Collector, data: Result<T, Error>) -> Self {
+        match data {
+            Ok(data) => Self::new(collector, data),
+            Err(e) => {
+                collector.error(e);
+                OwnedContext {
+                    collector,
+                    data: None,
+                    poisoned: true,
+                }
+            }
+        }
+    }
+
+    /// a borrowed context for the data
+    ///
+    /// the borrowed context reports to the collector of this context
+    pub fn context(&mut self) -> Context<'_, T> {
+        Context::from_parts(&mut self.collector, self.data.as_ref(), self.poisoned)
+    }
+
+    /// the collector of the context
+    pub fn collector(&mut self) -> &mut Collector {
+        &mut self.collector
+    }
+
+    /// the data of the context, see [`Context::data`]
+    pub fn data(&self) -> Option<&T> {
+        self.data.as_ref()
+    }
+
+    /// checks if the context has data, see [`Context::has_data`]
+    pub fn has_data(&self) -> bool {
+        self.data.is_some()
+    }
+
+    /// checks if the context has no data because parsing it failed, see [`Context::is_poisoned`]
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// lint the macro input, see [`Context::lint`]
+    pub fn lint<L: Lint<T> + ?Sized>(&mut self, lint: &L) -> bool {
+        self.context().lint(lint)
+    }
+
+    /// expand the macro and add the result to the collector, see [`Context::expand`]
+    pub fn expand<E: Expand<T>>(&mut self, expand: &E) {
+        self.context().expand(expand)
+    }
+
+    /// expand the macro and return the output, see [`Context::capture`]
+    pub fn capture<E: Expand<T>>(&mut self, expand: &E) -> Option<E::Output> {
+        self.context().capture(expand)
+    }
+
+    /// expand the macro and store the output in the collector, see [`Context::capture_into`]
+    pub fn capture_into<K, E>(&mut self, expand: &E) -> bool
+    where
+        K: CaptureKey,
+        E: Expand<T, Output = K::Value>,
+    {
+        self.context().capture_into::<K, E>(expand)
+    }
+
+    /// analyze the macro input and return the result, see [`Context::analyze`]
+    pub fn analyze<A: Analyze<T> + ?Sized>(&mut self, analyze: &A) -> Option<A::Output> {
+        self.context().analyze(analyze)
+    }
+
+    /// analyze the macro input and store the result in the collector, see [`Context::analyze_into`]
+    pub fn analyze_into<K, A>(&mut self, analyze: &A) -> bool
+    where
+        K: CaptureKey,
+        A: Analyze<T, Output = K::Value> + ?Sized,
+    {
+        self.context().analyze_into::<K, A>(analyze)
+    }
+
+    /// finish the expansion and return the result, see [`Collector::finish`]
+    pub fn finish(self) -> TokenStream {
+        self.collector.finish()
+    }
+
+    /// finish the expansion and return the result, adding `fallback` if errors were reported, see [`Collector::finish_with_fallback`]
+    pub fn finish_with_fallback(self, fallback: TokenStream) -> TokenStream {
+        self.collector.finish_with_fallback(fallback)
+    }
+
+    /// finish the expansion and return the result if no errors were reported, see [`Collector::finish_result`]
+    pub fn finish_result(self) -> Result<TokenStream, Vec<Error>> {
+        self.collector.finish_result()
+    }
+}
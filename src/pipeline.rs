@@ -0,0 +1,83 @@
+/// run lints and expands on a context in order
+///
+/// `pipeline! { ctx => lint A, expand B }` is the same as `ctx.lint(&A); ctx.expand(&B);`.
+/// the steps can be any expressions, eg. unit structs or calls to constructors. this works with [`Context`](crate::Context) and [`OwnedContext`](crate::OwnedContext)
+///
+/// # Example
+/// ```
+/// use macro_compose::{lints::{EnsureEnum, EnsureUnitVariants}, pipeline, Collector, Context, EchoExpand};
+/// use syn::{parse_quote, DeriveInput};
+///
+/// let mut collector = Collector::new();
+/// let input: DeriveInput = parse_quote!(enum Foo { Bar });
+/// let mut ctx = Context::new(&mut collector, input);
+/// pipeline! { ctx =>
+///     lint EnsureEnum::new(),
+///     lint EnsureUnitVariants::new(),
+///     expand EchoExpand,
+/// }
+/// assert!(collector.has_output());
+/// ```
+///
+/// unknown steps are rejected
+/// ```compile_fail
+/// # use macro_compose::{pipeline, Collector, Context, EchoExpand};
+/// # use syn::{parse_quote, DeriveInput};
+/// # let mut collector = Collector::new();
+/// # let input: DeriveInput = parse_quote!(enum Foo { Bar });
+/// # let mut ctx = Context::new(&mut collector, input);
+/// pipeline! { ctx =>
+///     capture EchoExpand,
+/// }
+/// ```
+///
+/// steps have to be separated by commas
+/// ```compile_fail
+/// # use macro_compose::{lints::EnsureEnum, pipeline, Collector, Context, EchoExpand};
+/// # use syn::{parse_quote, DeriveInput};
+/// # let mut collector = Collector::new();
+/// # let input: DeriveInput = parse_quote!(enum Foo { Bar });
+/// # let mut ctx = Context::new(&mut collector, input);
+/// pipeline! { ctx =>
+///     lint EnsureEnum::new()
+///     expand EchoExpand,
+/// }
+/// ```
+#[macro_export]
+macro_rules! pipeline {
+    ($ctx:expr => $($steps:tt)*) => {{
+        let ctx = &mut $ctx;
+        $crate::pipeline!(@steps ctx; $($steps)*);
+    }};
+    (@steps $ctx:ident;) => {};
+    (@steps $ctx:ident; lint $($steps:tt)*) => {
+        $crate::pipeline!(@lint $ctx; $($steps)*);
+    };
+    (@steps $ctx:ident; expand $($steps:tt)*) => {
+        $crate::pipeline!(@expand $ctx; $($steps)*);
+    };
+    (@steps $ctx:ident; $step:tt $($steps:tt)*) => {
+        compile_error!(concat!(
+            "unexpected `",
+            stringify!($step),
+            "` in `pipeline!`, expected `lint <expr>` or `expand <expr>`"
+        ));
+    };
+    (@lint $ctx:ident; $lint:expr $(, $($steps:tt)*)?) => {
+        $ctx.lint(&$lint);
+        $crate::pipeline!(@steps $ctx; $($($steps)*)?);
+    };
+    (@expand $ctx:ident; $expand:expr $(, $($steps:tt)*)?) => {
+        $ctx.expand(&$expand);
+        $crate::pipeline!(@steps $ctx; $($($steps)*)?);
+    };
+    (@$step:ident $ctx:ident;) => {
+        compile_error!(concat!("expected an expression after `", stringify!($step), "` in `pipeline!`"));
+    };
+    (@$step:ident $ctx:ident; $($steps:tt)*) => {
+        compile_error!("expected `,` between the steps of `pipeline!`");
+    };
+    ($($tokens:tt)*) => {
+        compile_error!("expected `pipeline! { ctx => lint <expr>, expand <expr>, ... }`");
+    };
+}
@@ -0,0 +1,75 @@
+use std::cell::RefCell;
+use syn::Error;
+
+use crate::Collector;
+
+thread_local! {
+    /// the errors reported in the active scopes, the innermost scope is last
+    static SCOPES: RefCell<Vec<Vec<Error>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// report an error to the collector of the innermost active scope, see [`Collector::enter_scope`]
+///
+/// the error is reported to the collector once the scope is left.
+/// this is useful to report errors from deeply nested helper functions without passing a `&mut Collector` to them
+///
+/// # Panics
+/// panics if no scope is active on the current thread, use [`try_report`] to handle that case
+///
+/// # Example
+/// ```
+/// use macro_compose::{report, Collector};
+/// use syn::{Error, Ident};
+///
+/// fn check_name(ident: &Ident) {
+///     if ident.to_string().starts_with('_') {
+///         report(Error::new_spanned(ident, "names must not start with an underscore"));
+///     }
+/// }
+///
+/// let ident: Ident = syn::parse_quote!(_foo);
+///
+/// let mut collector = Collector::new();
+/// collector.enter_scope(|| check_name(&ident));
+/// assert!(collector.has_errors());
+/// ```
+pub fn report(error: Error) {
+    if try_report(error).is_err() {
+        panic!("`macro_compose::report` was called outside of `Collector::enter_scope`");
+    }
+}
+
+/// report an error to the collector of the innermost active scope, see [`Collector::enter_scope`]
+///
+/// returns the error if no scope is active on the current thread
+pub fn try_report(error: Error) -> Result<(), Error> {
+    SCOPES.with(|scopes| match scopes.borrow_mut().last_mut() {
+        Some(scope) => {
+            scope.push(error);
+            Ok(())
+        }
+        None => Err(error),
+    })
+}
+
+/// leaves the scope when dropped and reports its errors to the collector
+///
+/// this also runs if the scope is left by a panic
+struct Guard<'a> {
+    collector: &'a mut Collector,
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        let errors = SCOPES
+            .with(|scopes| scopes.borrow_mut().pop())
+            .expect("the scope is still active");
+        self.collector.extend(errors);
+    }
+}
+
+pub(crate) fn enter<R>(collector: &mut Collector, f: impl FnOnce() -> R) -> R {
+    SCOPES.with(|scopes| scopes.borrow_mut().push(Vec::new()));
+    let _guard = Guard { collector };
+    f()
+}
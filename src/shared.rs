@@ -0,0 +1,144 @@
+use proc_macro2::TokenStream;
+use std::{
+    cell::{RefCell, RefMut},
+    fmt::{self, Debug, Formatter},
+    rc::Rc,
+};
+use syn::Error;
+
+use crate::Collector;
+
+/// a [`Collector`] that can be shared between helpers
+///
+/// cloning a shared collector is cheap and all clones report to the same collector.
+/// this is useful if several helpers need to report errors, but passing a `&mut Collector` around is awkward
+///
+/// the collector is only borrowed for the duration of a call.
+/// calling any method of a shared collector while it is borrowed through [`SharedCollector::with`] or [`SharedCollector::borrow_mut`] panics,
+/// use the `&mut Collector` passed to `with` instead
+///
+/// # Example
+/// ```
+/// use macro_compose::{Collector, Context, Lint, SharedCollector};
+/// use syn::{parse_quote, DeriveInput, Generics, Ident};
+///
+/// struct NoGenericsLint;
+///
+/// impl Lint<Generics> for NoGenericsLint {
+///     fn lint(&self, input: &Generics, c: &mut Collector) {
+///         if !input.params.is_empty() {
+///             c.error_spanned(input, "generics are not supported");
+///         }
+///     }
+/// }
+///
+/// struct NoUnderscoreLint;
+///
+/// impl Lint<Ident> for NoUnderscoreLint {
+///     fn lint(&self, input: &Ident, c: &mut Collector) {
+///         if input.to_string().starts_with('_') {
+///             c.error_spanned(input, "names must not start with an underscore");
+///         }
+///     }
+/// }
+///
+/// fn lint_generics(c: SharedCollector, input: &DeriveInput) {
+///     c.with(|c| Context::new_by_ref(c, &input.generics).lint(&NoGenericsLint));
+/// }
+///
+/// fn lint_ident(c: SharedCollector, input: &DeriveInput) {
+///     c.with(|c| Context::new_by_ref(c, &input.ident).lint(&NoUnderscoreLint));
+/// }
+///
+/// let input: DeriveInput = parse_quote!(struct _Foo<T>(T););
+///
+/// let collector = SharedCollector::new();
+/// lint_generics(collector.clone(), &input);
+/// lint_ident(collector.clone(), &input);
+/// assert_eq!(collector.error_count(), 2);
+///
+/// let output = collector.finish();
+/// ```
+#[derive(Clone, Default)]
+pub struct SharedCollector {
+    inner: Rc<RefCell<Collector>>,
+}
+
+impl SharedCollector {
+    /// create a new shared collector
+    pub fn new() -> Self {
+        Self::from(Collector::new())
+    }
+
+    /// report an error, see [`Collector::error`]
+    pub fn error(&self, e: Error) {
+        self.borrow_mut().error(e);
+    }
+
+    /// report a warning, see [`Collector::warning`]
+    pub fn warning(&self, e: Error) {
+        self.borrow_mut().warning(e);
+    }
+
+    /// checks if any errors have been reported yet, see [`Collector::has_errors`]
+    pub fn has_errors(&self) -> bool {
+        self.inner.borrow().has_errors()
+    }
+
+    /// the number of errors reported so far, see [`Collector::error_count`]
+    pub fn error_count(&self) -> usize {
+        self.inner.borrow().error_count()
+    }
+
+    /// borrow the collector
+    ///
+    /// # Panics
+    /// panics if the collector is already borrowed, eg. when called inside [`SharedCollector::with`]
+    pub fn borrow_mut(&self) -> RefMut<'_, Collector> {
+        self.inner.borrow_mut()
+    }
+
+    /// run `f` with the collector
+    ///
+    /// # Panics
+    /// panics if the collector is already borrowed, eg. when called inside another call to `with`
+    pub fn with<R>(&self, f: impl FnOnce(&mut Collector) -> R) -> R {
+        f(&mut self.borrow_mut())
+    }
+
+    /// get the collector back if this is the last clone
+    pub fn try_unwrap(self) -> Result<Collector, Self> {
+        Rc::try_unwrap(self.inner)
+            .map(RefCell::into_inner)
+            .map_err(|inner| SharedCollector { inner })
+    }
+
+    /// take the collector out and finish it, see [`Collector::finish`]
+    ///
+    /// other clones are left with an empty collector
+    pub fn finish(self) -> TokenStream {
+        let collector = match self.try_unwrap() {
+            Ok(collector) => collector,
+            Err(shared) => std::mem::take(&mut *shared.borrow_mut()),
+        };
+        collector.finish()
+    }
+}
+
+impl Debug for SharedCollector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("SharedCollector");
+        match self.inner.try_borrow() {
+            Ok(collector) => s.field("error_count", &collector.error_count()).finish(),
+            Err(_) => s.finish_non_exhaustive(),
+        }
+    }
+}
+
+impl From<Collector> for SharedCollector {
+    fn from(collector: Collector) -> Self {
+        SharedCollector {
+            inner: Rc::new(RefCell::new(collector)),
+        }
+    }
+}
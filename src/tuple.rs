@@ -0,0 +1,52 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+
+use crate::{Collector, Expand, Lint};
+
+/// implement `Lint` and `Expand` for tuples
+///
+/// the lints of a tuple run in order. the outputs of the expands are concatenated,
+/// once an error has been reported the remaining expands are skipped and the tuple doesn't produce an output
+macro_rules! impl_tuple {
+    ($($name:ident),+) => {
+        impl<I, $($name: Lint<I>),+> Lint<I> for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn lint(&self, input: &I, c: &mut Collector) {
+                let ($($name,)+) = self;
+                $($name.lint(input, c);)+
+            }
+        }
+
+        impl<I, $($name: Expand<I>),+> Expand<I> for ($($name,)+) {
+            type Output = TokenStream;
+
+            #[allow(non_snake_case)]
+            fn expand(&self, input: &I, c: &mut Collector) -> Option<Self::Output> {
+                let ($($name,)+) = self;
+                let start = c.error_count();
+                let mut output = None;
+                $(
+                    if c.error_count() != start {
+                        return None;
+                    }
+                    if let Some(res) = $name.expand(input, c) {
+                        res.to_tokens(output.get_or_insert_with(TokenStream::new));
+                    }
+                )+
+                if c.error_count() != start {
+                    return None;
+                }
+                output
+            }
+        }
+    };
+}
+
+impl_tuple!(A);
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
+impl_tuple!(A, B, C, D, E);
+impl_tuple!(A, B, C, D, E, F);
+impl_tuple!(A, B, C, D, E, F, G);
+impl_tuple!(A, B, C, D, E, F, G, H);
@@ -1,5 +1,17 @@
-use macro_compose::{Collector, Context, Expand, Lint, Nothing};
-use syn::{parse_quote, Error, ItemConst};
+mod common;
+
+use macro_compose::{
+    lint_fn, lints::EnsureUnitVariants, only_if, pipeline, unless, with_cfg, Analyze, BoxedExpand,
+    CaptureKey, CaptureResult, CatchPanic, CfgAware, Collector, Context, EchoExpand, Expand,
+    ExpandIter, ExpandMut, ExpandPass, ExpectError, ForEachField, ForEachVariant, Lint, LintExt,
+    LintMut, LintPass, Nothing, OwnedContext, Pass, Severity, WithCaptured,
+};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    parse_quote, punctuated::Punctuated, Data, DataEnum, DeriveInput, Error, Field, Fields, Item,
+    ItemConst, ItemImpl, ItemMod, ItemStruct, Token, Type, Variant,
+};
 
 #[test]
 fn basic() {
@@ -11,7 +23,7 @@ fn basic() {
     let mut ctx = Context::new(&mut collector, data);
     ctx.lint(&NoOpLint);
     ctx.expand(&NoOpExpand);
-    assert_eq!(collector.has_errors(), false);
+    collector.expect_success();
 }
 
 struct NoOpLint;
@@ -40,7 +52,7 @@ fn test_expand_disable() {
     let mut ctx = Context::new(&mut collector, data);
     ctx.lint(&AlwaysErrorLint);
     ctx.expand(&PanickingExpand);
-    assert_eq!(collector.has_errors(), true);
+    collector.expect_errors(1);
 }
 
 #[test]
@@ -72,3 +84,2505 @@ impl Expand<ItemConst> for PanickingExpand {
         unreachable!()
     }
 }
+
+#[test]
+fn test_warning() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.lint(&AlwaysWarningLint));
+    ctx.expand(&EchoExpand);
+
+    let output = collector.expect_success().to_string();
+    assert!(output.contains("some warning message"));
+    assert!(output.contains("const FOO : bool = true ;"));
+}
+
+#[test]
+fn test_warning_and_error() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&AlwaysWarningLint);
+    ctx.lint(&AlwaysErrorLint);
+    ctx.expand(&PanickingExpand);
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_severity_warning() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.lint(&AlwaysErrorLint.with_severity(Severity::Warning)));
+    ctx.expand(&EchoExpand);
+
+    let output = collector.expect_success().to_string();
+    assert!(output.contains("some error message"));
+    assert!(output.contains("const FOO : bool = true ;"));
+}
+
+#[test]
+fn test_severity_warning_keeps_help() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.lint(&HelpfulErrorLint.with_severity(Severity::Warning)));
+
+    let output = collector.expect_success().to_string();
+    assert!(output.contains("some error message"));
+    assert!(output.contains("help: try something else"));
+}
+
+#[test]
+fn test_severity_error() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(!ctx.lint(&AlwaysErrorLint.with_severity(Severity::Error)));
+    ctx.expand(&PanickingExpand);
+    collector.expect_errors(1);
+}
+
+struct HelpfulErrorLint;
+
+impl Lint<ItemConst> for HelpfulErrorLint {
+    fn lint(&self, i: &ItemConst, c: &mut Collector) {
+        c.error_with_help(
+            Error::new_spanned(i, "some error message"),
+            "try something else",
+        );
+    }
+}
+
+struct AlwaysWarningLint;
+
+impl Lint<ItemConst> for AlwaysWarningLint {
+    fn lint(&self, i: &ItemConst, c: &mut Collector) {
+        c.warning(Error::new_spanned(i, "some warning message"));
+    }
+}
+
+#[test]
+fn test_dedup_errors() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new_dedup();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    ctx.expand(&PanickingExpand);
+    assert!(collector.has_errors());
+
+    let output = collector.finish().to_string();
+    assert_eq!(output.matches("compile_error").count(), 1);
+}
+
+#[test]
+fn test_no_dedup_errors() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&AlwaysErrorLint);
+    ctx.lint(&AlwaysErrorLint);
+
+    let output = collector.finish().to_string();
+    assert_eq!(output.matches("compile_error").count(), 2);
+}
+
+#[test]
+fn test_error_count() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert_eq!(ctx.lint_counted(&NoOpLint), Some(0));
+    assert_eq!(ctx.lint_counted(&ErrorsLint(2)), Some(2));
+    assert_eq!(ctx.lint_counted(&ErrorsLint(3)), Some(3));
+    assert_eq!(collector.error_count(), 5);
+
+    let mut ctx = Context::<ItemConst>::new_empty(&mut collector);
+    assert_eq!(ctx.lint_counted(&ErrorsLint(1)), None);
+    assert_eq!(collector.error_count(), 5);
+}
+
+struct ErrorsLint(usize);
+
+impl Lint<ItemConst> for ErrorsLint {
+    fn lint(&self, i: &ItemConst, c: &mut Collector) {
+        for _ in 0..self.0 {
+            c.error(Error::new_spanned(i, "some error message"));
+        }
+    }
+}
+
+#[test]
+fn test_take_errors() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&AlwaysErrorLint);
+    ctx.expand(&PanickingExpand);
+
+    let errors = collector.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "some error message");
+    assert!(!collector.has_errors());
+
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&EchoExpand);
+    let output = collector.finish().to_string();
+    assert!(!output.contains("compile_error"));
+    assert!(output.contains("const FOO"));
+}
+
+#[test]
+fn test_catch_panic() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&CatchPanic(PanickingExpand));
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(
+        errors[0].to_string(),
+        "expansion panicked: internal error: entered unreachable code"
+    );
+}
+
+#[test]
+fn test_catch_panic_non_string() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&CatchPanic(NonStringPanicExpand));
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(errors[0].to_string(), "expansion panicked");
+}
+
+struct NonStringPanicExpand;
+
+impl Expand<ItemConst> for NonStringPanicExpand {
+    type Output = Nothing;
+
+    fn expand(&self, _: &ItemConst, _: &mut Collector) -> Option<Self::Output> {
+        std::panic::panic_any(42)
+    }
+}
+
+#[test]
+fn test_has_output() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&NoOpExpand);
+    ctx.lint(&AlwaysWarningLint);
+    assert!(!collector.has_output());
+
+    let data: ItemConst = parse_quote!(
+        const BAR: u8 = 0;
+    );
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&EchoExpand);
+    assert!(collector.has_output());
+}
+
+#[test]
+fn test_has_output_errors() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&AlwaysErrorLint);
+    assert!(!collector.has_output());
+}
+
+#[test]
+#[should_panic(
+    expected = "expected no errors, but 2 errors were reported:\n  some error message at"
+)]
+fn test_expect_success_panics() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&ErrorsLint(2));
+    collector.expect_success();
+}
+
+#[test]
+#[should_panic(expected = "expected 2 error(s), but 1 error was reported:")]
+fn test_expect_errors_panics() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&AlwaysErrorLint);
+    collector.expect_errors(2);
+}
+
+struct IdentConstExpand;
+
+impl Expand<Ident> for IdentConstExpand {
+    type Output = ItemConst;
+
+    fn expand(&self, input: &Ident, _: &mut Collector) -> Option<Self::Output> {
+        let name = input.to_string();
+        Some(parse_quote!(
+            const NAME: &str = #name;
+        ))
+    }
+}
+
+#[test]
+fn test_map() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+
+    let mut collector = Collector::new();
+    let ctx = Context::new(&mut collector, data);
+    let mut ctx = ctx.map(|input| input.ident.clone());
+    ctx.expand(&IdentConstExpand);
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output, "const NAME : & str = \"Foo\" ;");
+}
+
+#[test]
+fn test_map_keeps_error_gate() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+
+    let mut collector = Collector::new();
+    collector.error(Error::new(Span::call_site(), "some error message"));
+    let mut ctx = Context::new(&mut collector, data).map(|input| input.ident.clone());
+    ctx.expand(&IdentConstExpand);
+    assert!(!collector.has_output());
+}
+
+#[test]
+fn test_try_map() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data).try_map(|input| match &input.data {
+        Data::Enum(e) => Ok(e.clone()),
+        _ => Err(Error::new_spanned(input, "expected an enum")),
+    });
+    assert_eq!(ctx.lint_counted(&NoOpEnumLint), None);
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(errors[0].to_string(), "expected an enum");
+}
+
+struct NoOpEnumLint;
+
+impl Lint<DataEnum> for NoOpEnumLint {
+    fn lint(&self, _: &DataEnum, _: &mut Collector) {}
+}
+
+#[test]
+fn test_data() {
+    let data: DeriveInput = parse_quote!(
+        enum Foo {}
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data.clone());
+    assert!(matches!(ctx.data().map(|d| &d.data), Some(Data::Enum(_))));
+
+    ctx.data_mut().unwrap().ident = parse_quote!(Bar);
+    assert_eq!(ctx.data().unwrap().ident, "Bar");
+
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert_eq!(ctx.data().unwrap().ident, "Foo");
+    assert!(ctx.data_mut().is_none());
+
+    let mut ctx = Context::<DeriveInput>::new_empty(&mut collector);
+    assert!(ctx.data().is_none());
+    assert!(ctx.data_mut().is_none());
+}
+
+#[test]
+fn test_into_inner() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+
+    let mut collector = Collector::new();
+    let ctx = Context::new(&mut collector, data.clone());
+    assert_eq!(ctx.into_inner(), Some(data.clone()));
+
+    let ctx = Context::new_by_ref(&mut collector, &data);
+    assert_eq!(ctx.to_inner(), Some(data.clone()));
+    assert_eq!(ctx.into_inner(), None);
+
+    let ctx = Context::<DeriveInput>::new_empty(&mut collector);
+    assert_eq!(ctx.to_inner(), None);
+    assert_eq!(ctx.into_inner(), None);
+
+    collector.expect_success();
+}
+
+#[test]
+fn test_new_parse_attr() {
+    let mut collector = Collector::new();
+    let ctx = Context::<(Ident, ItemConst)>::new_parse_attr2(
+        &mut collector,
+        quote!(foo),
+        quote!(
+            const FOO: bool = true;
+        ),
+    );
+    let (attr, item) = ctx.into_inner().unwrap();
+    assert_eq!(attr, "foo");
+    assert_eq!(item.ident, "FOO");
+    collector.expect_success();
+}
+
+#[test]
+fn test_new_parse_attr_bad_attr() {
+    let mut collector = Collector::new();
+    let ctx = Context::<(Ident, ItemConst)>::new_parse_attr2(
+        &mut collector,
+        quote!(1),
+        quote!(
+            const FOO: bool = true;
+        ),
+    );
+    assert!(ctx.data().is_none());
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_new_parse_attr_bad_item() {
+    let mut collector = Collector::new();
+    let ctx =
+        Context::<(Ident, ItemConst)>::new_parse_attr2(&mut collector, quote!(foo), quote!(fn));
+    assert!(ctx.data().is_none());
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_new_parse_attr_both_bad() {
+    let mut collector = Collector::new();
+    let ctx = Context::<(Ident, ItemConst)>::new_parse_attr2(&mut collector, quote!(1), quote!(fn));
+    assert!(ctx.data().is_none());
+    collector.expect_errors(2);
+}
+
+#[test]
+fn test_lint_all() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.lint_all(&[&NoOpLint, &AlwaysWarningLint]));
+    assert!(!ctx.lint_all(&[&AlwaysErrorLint, &NoOpLint, &ErrorsLint(2)]));
+    assert_eq!(collector.error_count(), 3);
+}
+
+#[test]
+fn test_boxed_lint() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let lints: Vec<Box<dyn Lint<ItemConst>>> = vec![Box::new(NoOpLint), Box::new(ErrorsLint(2))];
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    for lint in lints.iter() {
+        ctx.lint(lint);
+    }
+    assert_eq!(collector.error_count(), 2);
+}
+
+struct ConstNameExpand;
+
+impl Expand<ItemConst> for ConstNameExpand {
+    type Output = Ident;
+
+    fn expand(&self, input: &ItemConst, _: &mut Collector) -> Option<Self::Output> {
+        Some(input.ident.clone())
+    }
+}
+
+struct AlwaysErrorExpand;
+
+impl Expand<ItemConst> for AlwaysErrorExpand {
+    type Output = Nothing;
+
+    fn expand(&self, i: &ItemConst, c: &mut Collector) -> Option<Self::Output> {
+        c.error(Error::new_spanned(i, "some error message"));
+        None
+    }
+}
+
+#[test]
+fn test_expand_all() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand_all(&[&ConstNameExpand, &NoOpExpand, &EchoExpand, &ConstNameExpand]);
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output, "FOO const FOO : bool = true ; FOO");
+}
+
+#[test]
+fn test_expand_all_skips_after_error() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand_all(&[&ConstNameExpand, &AlwaysErrorExpand, &PanickingExpand]);
+
+    let (output, errors) = collector.into_parts();
+    assert_eq!(output.to_string(), "FOO");
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_fork() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+    let attr: Ident = parse_quote!(bar);
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    let name = ctx.fork(attr, |ctx| {
+        ctx.expand(&EchoExpand);
+        ctx.data().unwrap().to_string()
+    });
+    assert_eq!(name, "bar");
+    assert_eq!(ctx.data().unwrap().ident, "FOO");
+    ctx.expand(&ConstNameExpand);
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output, "bar FOO");
+}
+
+#[test]
+fn test_fork_error_gates_parent() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+    let attr: ItemConst = parse_quote!(
+        const BAR: bool = false;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.fork(attr, |ctx| ctx.lint(&AlwaysErrorLint));
+    ctx.expand(&PanickingExpand);
+
+    collector.expect_errors(1);
+}
+
+struct ImplExpand;
+
+impl Expand<ItemConst> for ImplExpand {
+    type Output = ItemImpl;
+
+    fn expand(&self, _: &ItemConst, _: &mut Collector) -> Option<Self::Output> {
+        Some(parse_quote!(
+            impl Foo for Bar {}
+        ))
+    }
+}
+
+#[test]
+fn test_capture_parse() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    let item: Option<Item> = ctx.capture_parse(&ImplExpand);
+    assert!(matches!(item, Some(Item::Impl(_))));
+
+    let ident: Option<Ident> = ctx.capture_parse(&ImplExpand);
+    assert!(ident.is_none());
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_capture_parse_skipped_after_error() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&AlwaysErrorLint);
+    let item: Option<Item> = ctx.capture_parse(&PanickingExpand);
+    assert!(item.is_none());
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_expand_if() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand_if(|input| input.ident == "FOO", &ConstNameExpand);
+    ctx.expand_if(|input| input.ident == "BAR", &PanickingExpand);
+
+    let mut ctx = Context::<ItemConst>::new_empty(&mut collector);
+    ctx.expand_if(|_| unreachable!(), &PanickingExpand);
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output, "FOO");
+}
+
+struct DeriveIdentExpand;
+
+impl Expand<DeriveInput> for DeriveIdentExpand {
+    type Output = Ident;
+
+    fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
+        Some(input.ident.clone())
+    }
+}
+
+#[test]
+fn test_expand_if_attr() {
+    let data: DeriveInput = parse_quote!(
+        #[from_str(display)]
+        #[foo::bar]
+        struct Foo;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand_if_attr("from_str", &DeriveIdentExpand);
+    ctx.expand_if_attr("foo::bar", &DeriveIdentExpand);
+    ctx.expand_if_attr("foo", &DeriveIdentExpand);
+    ctx.expand_if_attr("bar", &DeriveIdentExpand);
+    ctx.expand_if_attr("foo::bar::baz", &DeriveIdentExpand);
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output, "Foo Foo");
+}
+
+#[test]
+fn test_set_update_modify() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.lint(&NoOpLint));
+    assert!(ctx.modify(|input| input.ident = parse_quote!(BAR)));
+    ctx.expand(&ConstNameExpand);
+    assert!(ctx.update(|mut input| {
+        input.ident = parse_quote!(BAZ);
+        input
+    }));
+    ctx.expand(&ConstNameExpand);
+    ctx.set(parse_quote!(
+        const QUX: bool = true;
+    ));
+    ctx.expand(&ConstNameExpand);
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output, "BAR BAZ QUX");
+}
+
+#[test]
+fn test_update_borrowed() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(!ctx.modify(|_| unreachable!()));
+    assert!(!ctx.update(|_| unreachable!()));
+    assert_eq!(ctx.data(), Some(&data));
+
+    let mut ctx = Context::<ItemConst>::new_empty(&mut collector);
+    assert!(!ctx.update(|_| unreachable!()));
+    ctx.set(data.clone());
+    assert_eq!(ctx.data(), Some(&data));
+}
+
+#[test]
+fn test_set_keeps_error_gate() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data.clone());
+    ctx.lint(&AlwaysErrorLint);
+    ctx.set(data);
+    ctx.expand(&PanickingExpand);
+    collector.expect_errors(1);
+}
+
+struct UnitVariantLint;
+
+impl Lint<Variant> for UnitVariantLint {
+    fn lint(&self, input: &Variant, c: &mut Collector) {
+        if input.fields != Fields::Unit {
+            c.error_spanned(input, "expected a unit variant");
+        }
+    }
+}
+
+struct VariantNameExpand;
+
+impl Expand<Variant> for VariantNameExpand {
+    type Output = Ident;
+
+    fn expand(&self, input: &Variant, _: &mut Collector) -> Option<Self::Output> {
+        Some(input.ident.clone())
+    }
+}
+
+#[test]
+fn test_for_each_variant() {
+    let data: DeriveInput = parse_quote!(
+        enum Foo {
+            A,
+            B,
+            C,
+        }
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.for_each_variant(|ctx| ctx.expand(&VariantNameExpand));
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output, "A B C");
+}
+
+#[test]
+fn test_for_each_variant_error() {
+    let mut collector = Collector::new();
+    let mut ctx = Context::<DeriveInput>::new_parse_str(&mut collector, "enum Foo { A, B(u8), C }");
+    let mut linted = Vec::new();
+    ctx.for_each_variant(|ctx| {
+        ctx.lint(&UnitVariantLint);
+        linted.push(ctx.data().unwrap().ident.to_string());
+    });
+    assert_eq!(linted, ["A", "B", "C"]);
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(errors[0].to_string(), "expected a unit variant");
+    assert_eq!(errors[0].span().start().column, 14);
+}
+
+#[test]
+fn test_for_each_variant_not_an_enum() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.for_each_variant(|_| unreachable!());
+    collector.expect_success();
+}
+
+#[test]
+fn test_new_parse_str() {
+    let mut collector = Collector::new();
+    let mut ctx = Context::<ItemConst>::new_parse_str(&mut collector, "const FOO: bool = true;");
+    ctx.expand(&ConstNameExpand);
+    assert_eq!(collector.expect_success().to_string(), "FOO");
+
+    let mut collector = Collector::new();
+    let ctx = Context::<ItemConst>::new_parse_str(&mut collector, "fn foo() {}");
+    assert!(ctx.data().is_none());
+    collector.expect_errors(1);
+
+    let mut collector = Collector::new();
+    let ctx = Context::<ItemConst>::new_parse_str(&mut collector, "const FOO: bool = (;");
+    assert!(ctx.data().is_none());
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_lint_collect() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    let errors = ctx.lint_collect(&ErrorsLint(2));
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].to_string(), "some error message");
+    assert!(ctx.lint_collect(&NoOpLint).is_empty());
+    assert_eq!(ctx.lint_collect(&AlwaysErrorLint).len(), 1);
+    assert_eq!(collector.error_count(), 3);
+}
+
+#[test]
+fn test_try_capture() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert_eq!(
+        ctx.try_capture(&ConstNameExpand),
+        CaptureResult::Produced(parse_quote!(FOO))
+    );
+    assert!(matches!(
+        ctx.try_capture(&NoOpExpand),
+        CaptureResult::Nothing
+    ));
+    ctx.lint(&AlwaysErrorLint);
+    assert!(matches!(
+        ctx.try_capture(&PanickingExpand),
+        CaptureResult::SkippedDueToErrors
+    ));
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::<ItemConst>::new_empty(&mut collector);
+    assert!(matches!(
+        ctx.try_capture(&PanickingExpand),
+        CaptureResult::NoData
+    ));
+}
+
+struct ConstName;
+
+impl CaptureKey for ConstName {
+    type Value = Ident;
+}
+
+struct ConstNameStructExpand;
+
+impl Expand<(&ItemConst, &Ident)> for ConstNameStructExpand {
+    type Output = ItemStruct;
+
+    fn expand(&self, (_, name): &(&ItemConst, &Ident), _: &mut Collector) -> Option<Self::Output> {
+        Some(parse_quote!(
+            struct #name;
+        ))
+    }
+}
+
+#[test]
+fn test_capture_into() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.capture_into::<ConstName, _>(&ConstNameExpand));
+    ctx.expand(&WithCaptured::<ConstName, _>::new(ConstNameStructExpand));
+    ctx.expand(&WithCaptured::<ConstName, _>::new(ConstNameStructExpand));
+    assert_eq!(collector.captured::<ConstName>().unwrap(), "FOO");
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output, "struct FOO ; struct FOO ;");
+}
+
+struct CapturedLookupExpand;
+
+impl Expand<(&ItemConst, &Ident)> for CapturedLookupExpand {
+    type Output = Nothing;
+
+    fn expand(&self, (_, name): &(&ItemConst, &Ident), c: &mut Collector) -> Option<Self::Output> {
+        assert_eq!(c.captured::<ConstName>(), Some(*name));
+        Some(Nothing)
+    }
+}
+
+struct PanickingCapturedExpand;
+
+impl Expand<(&ItemConst, &Ident)> for PanickingCapturedExpand {
+    type Output = Nothing;
+
+    fn expand(&self, _: &(&ItemConst, &Ident), _: &mut Collector) -> Option<Self::Output> {
+        panic!("oops")
+    }
+}
+
+#[test]
+fn test_captured_value_lent() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.capture_into::<ConstName, _>(&ConstNameExpand));
+    ctx.expand(&WithCaptured::<ConstName, _>::new(CapturedLookupExpand));
+    ctx.expand(&CatchPanic(WithCaptured::<ConstName, _>::new(
+        PanickingCapturedExpand,
+    )));
+    assert_eq!(collector.captured::<ConstName>().unwrap(), "FOO");
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_captured_value_missing() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&WithCaptured::<ConstName, _>::new(ConstNameStructExpand));
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(
+        errors[0].to_string(),
+        "no value has been captured for `basic::ConstName`"
+    );
+}
+
+#[test]
+fn test_has_data_and_is_poisoned() {
+    let mut collector = Collector::new();
+    collector.error(Error::new(Span::call_site(), "reported by another macro"));
+
+    let mut ctx = Context::<ItemConst>::new_parse_str(&mut collector, "const FOO: bool = true;");
+    assert!(ctx.has_data());
+    assert!(!ctx.is_poisoned());
+    assert!(ctx.lint(&NoOpLint));
+
+    let mut ctx = Context::<ItemConst>::new_parse_str(&mut collector, "fn foo() {}");
+    assert!(!ctx.has_data());
+    assert!(ctx.is_poisoned());
+    assert!(!ctx.lint(&NoOpLint));
+    assert_eq!(ctx.lint_counted(&NoOpLint), None);
+
+    let mut ctx = Context::<ItemConst>::new_empty(&mut collector);
+    assert!(!ctx.has_data());
+    assert!(!ctx.is_poisoned());
+    assert_eq!(ctx.lint_counted(&NoOpLint), None);
+
+    let ctx = Context::<(Ident, ItemConst)>::new_parse_attr2(&mut collector, quote!(1), quote!());
+    assert!(ctx.is_poisoned());
+
+    let ctx = Context::<ItemConst>::new_parse_str(&mut collector, "const FOO: bool = true;")
+        .try_map(|input| Err::<Ident, _>(Error::new_spanned(input, "some error message")));
+    assert!(ctx.is_poisoned());
+    assert!(ctx.map(|ident| ident.clone()).is_poisoned());
+}
+
+#[test]
+fn test_new_parse2_with() {
+    let mut collector = Collector::new();
+    let ctx = Context::new_parse2_with(
+        &mut collector,
+        quote!(a, b, c,),
+        Punctuated::<Ident, Token![,]>::parse_terminated,
+    );
+    let idents: Vec<_> = ctx
+        .data()
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(idents, ["a", "b", "c"]);
+    collector.expect_success();
+
+    let mut collector = Collector::new();
+    let ctx = Context::new_parse2_with(
+        &mut collector,
+        quote!(a, 1),
+        Punctuated::<Ident, Token![,]>::parse_terminated,
+    );
+    assert!(ctx.is_poisoned());
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_boxed_pipeline() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let lints: Vec<Box<dyn Lint<ItemConst>>> =
+        vec![Box::new(NoOpLint), Box::new(AlwaysWarningLint)];
+    let named: Box<dyn Expand<ItemConst, Output = Ident>> = Box::new(ConstNameExpand);
+    let expands = vec![
+        BoxedExpand::new(EchoExpand),
+        BoxedExpand::new(NoOpExpand),
+        BoxedExpand::new(named),
+    ];
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    for lint in &lints {
+        assert!(ctx.lint(lint.as_ref()));
+    }
+    for expand in &expands {
+        ctx.expand(expand);
+    }
+
+    let (output, errors) = collector.into_parts();
+    assert!(errors.is_empty());
+    let output = output.to_string();
+    assert!(output.starts_with("const FOO : bool = true ; FOO"));
+    assert!(output.contains("some warning message"));
+}
+
+struct MaxVariantsLint {
+    seen: usize,
+    max: usize,
+}
+
+impl LintMut<Variant> for MaxVariantsLint {
+    fn lint_mut(&mut self, input: &Variant, c: &mut Collector) {
+        self.seen += 1;
+        if self.seen > self.max {
+            c.error_spanned(input, "too many variants");
+        }
+    }
+}
+
+#[test]
+fn test_lint_mut() {
+    let mut collector = Collector::new();
+    let mut ctx = Context::<DeriveInput>::new_parse_str(&mut collector, "enum Foo { A, B, C, D }");
+    let mut lint = MaxVariantsLint { seen: 0, max: 2 };
+    let mut passed = Vec::new();
+    ctx.for_each_variant(|ctx| passed.push(ctx.lint_mut(&mut lint)));
+    assert_eq!(passed, [true, true, false, false]);
+    assert_eq!(lint.seen, 4);
+
+    let errors = collector.expect_errors(2);
+    assert_eq!(errors[0].span().start().column, 17);
+    assert_eq!(errors[1].span().start().column, 20);
+}
+
+struct CountingExpand(usize);
+
+impl ExpandMut<Variant> for CountingExpand {
+    type Output = ItemConst;
+
+    fn expand_mut(&mut self, input: &Variant, _: &mut Collector) -> Option<Self::Output> {
+        let ident = &input.ident;
+        let index = self.0;
+        self.0 += 1;
+        Some(parse_quote!(
+            const #ident: usize = #index;
+        ))
+    }
+}
+
+#[test]
+fn test_expand_mut() {
+    let data: DeriveInput = parse_quote!(
+        enum Foo {
+            A,
+            B,
+        }
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    let mut expand = CountingExpand(0);
+    ctx.for_each_variant(|ctx| ctx.expand_mut(&mut expand));
+    // every `Expand` is also an `ExpandMut`
+    ctx.expand_mut(&mut DeriveIdentExpand);
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(
+        output,
+        "const A : usize = 0usize ; const B : usize = 1usize ; Foo"
+    );
+}
+
+#[test]
+fn test_capture_all() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    let items = ctx.capture_all(&[&ImplExpand, &NoOpExpand, &EchoExpand, &ImplExpand]);
+    assert_eq!(items.len(), 3);
+
+    let module: ItemMod = parse_quote!(
+        mod generated {
+            #(#items)*
+        }
+    );
+    // the captured outputs aren't added to the collector
+    assert!(!collector.has_output());
+    assert_eq!(
+        module.to_token_stream().to_string(),
+        "mod generated { impl Foo for Bar { } const FOO : bool = true ; impl Foo for Bar { } }"
+    );
+}
+
+#[test]
+fn test_capture_all_skips_after_error() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    let items = ctx.capture_all(&[&ConstNameExpand, &AlwaysErrorExpand, &PanickingExpand]);
+    assert_eq!(items.len(), 1);
+    collector.expect_errors(1);
+}
+
+struct ImplPerVariantExpand;
+
+impl ExpandIter<DeriveInput> for ImplPerVariantExpand {
+    type Item = ItemImpl;
+
+    fn expand_iter(&self, input: &DeriveInput, c: &mut Collector) -> Option<Vec<Self::Item>> {
+        let variants = match &input.data {
+            Data::Enum(e) => &e.variants,
+            _ => {
+                c.error_spanned(input, "expected an enum");
+                return None;
+            }
+        };
+        let ident = &input.ident;
+        let impls = variants.iter().map(|v| {
+            let v = &v.ident;
+            parse_quote!(
+                impl Marker<#v> for #ident {}
+            )
+        });
+        Some(impls.collect())
+    }
+}
+
+#[test]
+fn test_expand_iter() {
+    let data: DeriveInput = parse_quote!(
+        enum Foo {
+            A,
+            B,
+            C,
+        }
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand_iter(&ImplPerVariantExpand);
+    ctx.expand(&DeriveIdentExpand);
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(
+        output,
+        "impl Marker < A > for Foo { } impl Marker < B > for Foo { } impl Marker < C > for Foo { } Foo"
+    );
+}
+
+#[test]
+fn test_expand_iter_error() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.capture_iter(&ImplPerVariantExpand).is_none());
+    ctx.expand(&DeriveIdentExpand);
+    assert!(!collector.has_output());
+    collector.expect_errors(1);
+}
+
+struct NoReferenceLint;
+
+impl Lint<Field> for NoReferenceLint {
+    fn lint(&self, input: &Field, c: &mut Collector) {
+        if let Type::Reference(_) = input.ty {
+            c.error_spanned(input, "references are not supported");
+        }
+    }
+}
+
+#[test]
+fn test_for_each_field() {
+    let mut collector = Collector::new();
+    let mut ctx =
+        Context::<DeriveInput>::new_parse_str(&mut collector, "struct Foo(u8, &'static str, u16);");
+    let mut indices = Vec::new();
+    ctx.for_each_field(|i, ctx| {
+        indices.push(i);
+        ctx.lint(&NoReferenceLint);
+    });
+    assert_eq!(indices, [0, 1, 2]);
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(errors[0].to_string(), "references are not supported");
+    assert_eq!(errors[0].span().start().column, 15);
+}
+
+#[test]
+fn test_for_each_field_not_a_struct() {
+    let data: DeriveInput = parse_quote!(
+        enum Foo {
+            A(u8),
+        }
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.for_each_field(|_, _| unreachable!());
+    collector.expect_success();
+}
+
+#[test]
+fn test_new_parse_preserving() {
+    let mut collector = Collector::new();
+    let ctx = Context::<ItemConst>::new_parse2_preserving(
+        &mut collector,
+        quote!(
+            struct Foo;
+        ),
+    );
+    assert!(ctx.is_poisoned());
+
+    let output = collector.finish().to_string();
+    assert!(output.starts_with("compile_error !"));
+    assert!(output.ends_with("struct Foo ;"));
+}
+
+#[test]
+fn test_new_parse_preserving_success() {
+    let mut collector = Collector::new();
+    let mut ctx = Context::<ItemConst>::new_parse2_preserving(
+        &mut collector,
+        quote!(
+            const FOO: bool = true;
+        ),
+    );
+    ctx.expand(&ConstNameExpand);
+    assert_eq!(collector.expect_success().to_string(), "FOO");
+}
+
+#[test]
+fn test_run_all() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let passes: Vec<Box<dyn Pass<ItemConst>>> = vec![
+        Box::new(ExpandPass(ConstNameExpand)),
+        Box::new(LintPass(NoOpLint)),
+        Box::new(ExpandPass(EchoExpand)),
+        Box::new(LintPass(AlwaysErrorLint)),
+        Box::new(LintPass(AlwaysWarningLint)),
+        Box::new(ExpandPass(PanickingExpand)),
+    ];
+    let passes: Vec<&dyn Pass<ItemConst>> = passes.iter().map(|pass| pass.as_ref()).collect();
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.run_all(&passes);
+
+    let (output, errors) = collector.into_parts();
+    assert_eq!(errors.len(), 1);
+    let output = output.to_string();
+    assert!(output.starts_with("FOO const FOO : bool = true ;"));
+    // the lint after the failing lint still ran
+    assert!(output.contains("some warning message"));
+}
+
+#[test]
+fn test_owned_context() {
+    let mut ctx = OwnedContext::<ItemConst>::new_parse2(
+        Collector::new(),
+        quote!(
+            const FOO: bool = true;
+        ),
+    );
+    assert!(ctx.has_data());
+    assert!(ctx.lint(&NoOpLint));
+    ctx.expand(&ConstNameExpand);
+    ctx.context().expand(&EchoExpand);
+    assert_eq!(
+        ctx.finish_result().unwrap().to_string(),
+        "FOO const FOO : bool = true ;"
+    );
+}
+
+#[test]
+fn test_owned_context_poisoned() {
+    let mut ctx = OwnedContext::<ItemConst>::new_parse2(
+        Collector::new(),
+        quote!(
+            struct Foo;
+        ),
+    );
+    assert!(ctx.is_poisoned());
+    assert!(ctx.context().is_poisoned());
+    assert!(!ctx.lint(&NoOpLint));
+    ctx.expand(&PanickingExpand);
+    assert_eq!(ctx.finish_result().unwrap_err().len(), 1);
+}
+
+struct NamedErrorExpand;
+
+impl Expand<ItemConst> for NamedErrorExpand {
+    type Output = Nothing;
+
+    fn expand(&self, i: &ItemConst, c: &mut Collector) -> Option<Self::Output> {
+        Context::new_by_ref(c, i).expand_named("inner", &AlwaysErrorExpand);
+        None
+    }
+}
+
+#[test]
+fn test_expand_named() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    ctx.expand_named("outer", &NamedErrorExpand);
+    // the errors returned by `Collector::errors` are undecorated
+    assert_eq!(
+        collector.errors().next().unwrap().to_string(),
+        "some error message"
+    );
+    let (_, errors) = collector.into_parts();
+    assert_eq!(
+        errors[0].to_string(),
+        "some error message (while expanding `inner`, while expanding `outer`)"
+    );
+}
+
+#[test]
+fn test_lint_named() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(!ctx.lint_named("always error", &AlwaysErrorLint));
+    collector.error(Error::new(Span::call_site(), "unnamed"));
+    let (_, errors) = collector.into_parts();
+    assert_eq!(
+        errors[0].to_string(),
+        "some error message (while linting `always error`)"
+    );
+    assert_eq!(errors[1].to_string(), "unnamed");
+}
+
+#[test]
+fn test_recover() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    assert_eq!(
+        ctx.try_capture(&EchoExpand),
+        CaptureResult::SkippedDueToErrors
+    );
+
+    ctx.recover();
+    ctx.expand(&ConstNameExpand);
+
+    // a new error after the recovery point blocks the expansion again
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    ctx.expand(&PanickingExpand);
+
+    assert!(collector.has_errors());
+    assert!(collector.has_new_errors());
+    let (output, errors) = collector.into_parts();
+    assert_eq!(output.to_string(), "FOO");
+    assert_eq!(errors.len(), 2);
+}
+
+#[test]
+fn test_expand_unchecked() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    ctx.expand(&ConstNameExpand);
+    ctx.expand_unchecked(&EchoExpand);
+    assert_eq!(
+        ctx.capture_unchecked(&ConstNameExpand).unwrap().to_string(),
+        "FOO"
+    );
+
+    let output = collector.finish().to_string();
+    assert!(output.starts_with("compile_error !"));
+    assert!(output.ends_with("const FOO : bool = true ;"));
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::<ItemConst>::new_empty(&mut collector);
+    assert!(ctx.capture_unchecked(&EchoExpand).is_none());
+}
+
+#[test]
+fn test_expand_to() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut side = TokenStream::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    ctx.expand(&EchoExpand);
+    ctx.expand_to(&ConstNameExpand, &mut side);
+    assert_eq!(side.to_string(), "FOO");
+
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    ctx.expand(&PanickingExpand);
+    ctx.expand_to(&PanickingExpand, &mut side);
+    assert_eq!(side.to_string(), "FOO");
+
+    let (output, errors) = collector.into_parts();
+    assert_eq!(output.to_string(), "const FOO : bool = true ;");
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_filter() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(ctx.filter(|c| c.ident == "FOO"));
+    assert!(!ctx.filter(|c| c.ident == "BAR"));
+    assert!(!ctx.has_data());
+    assert!(!ctx.is_poisoned());
+    assert!(!ctx.filter(|_| panic!("the predicate shouldn't be called")));
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    ctx.expand(&PanickingExpand);
+
+    assert!(!collector.has_errors());
+    assert!(collector.finish().is_empty());
+}
+
+struct Config {
+    suffix: &'static str,
+}
+
+struct ZippedExpand;
+
+impl Expand<(DeriveInput, Config)> for ZippedExpand {
+    type Output = Ident;
+
+    fn expand(
+        &self,
+        (input, config): &(DeriveInput, Config),
+        _: &mut Collector,
+    ) -> Option<Self::Output> {
+        Some(Ident::new(
+            &format!("{}{}", input.ident, config.suffix),
+            Span::call_site(),
+        ))
+    }
+}
+
+#[test]
+fn test_zip() {
+    let mut collector = Collector::new();
+    let ctx = Context::<DeriveInput>::new_parse2(
+        &mut collector,
+        quote!(
+            struct Foo;
+        ),
+    );
+    let mut ctx = ctx.zip(Some(Config { suffix: "Error" }));
+    assert!(ctx.has_data());
+    ctx.expand(&ZippedExpand);
+    assert_eq!(collector.finish().to_string(), "FooError");
+
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data).zip(None::<Config>);
+    assert!(!ctx.has_data());
+    ctx.expand(&ZippedExpand);
+
+    let mut ctx = Context::<DeriveInput>::new_parse2(
+        &mut collector,
+        quote!(
+            fn foo() {}
+        ),
+    )
+    .zip(Some(Config { suffix: "Error" }));
+    assert!(ctx.is_poisoned());
+    ctx.expand(&ZippedExpand);
+    assert_eq!(collector.into_parts().0.to_string(), "");
+}
+
+struct NoConstItemLint;
+
+impl Lint<Item> for NoConstItemLint {
+    fn lint(&self, input: &Item, c: &mut Collector) {
+        if let Item::Const(item) = input {
+            c.error_spanned(&item.ident, "constants are not supported");
+        }
+    }
+}
+
+#[test]
+fn test_new_from_tokens() {
+    let data: ItemConst = syn::parse_str("const FOO: bool = true;").unwrap();
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::<Item>::new_from_tokens(&mut collector, &data);
+    assert!(!ctx.lint(&NoConstItemLint));
+
+    let error = collector.errors().next().unwrap();
+    assert_eq!(
+        error.span().start().column,
+        data.ident.span().start().column
+    );
+
+    let mut collector = Collector::new();
+    let ctx = Context::<ItemStruct>::new_from_tokens(&mut collector, &data);
+    assert!(ctx.is_poisoned());
+    assert!(collector.has_errors());
+}
+
+#[test]
+fn test_helper_attrs() {
+    let data: DeriveInput = syn::parse_str(
+        r#"
+        #[helper(item)]
+        #[other]
+        enum Foo {
+            #[helper(rename = "bar")]
+            Bar {
+                #[helper]
+                field: u8,
+            },
+            #[helper = ]
+            Baz,
+        }
+        "#,
+    )
+    .unwrap();
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    let attrs: Vec<_> = ctx
+        .helper_attrs("helper")
+        .into_iter()
+        .map(|(_, meta)| meta.into_token_stream().to_string())
+        .collect();
+    assert_eq!(
+        attrs,
+        ["helper (item)", "helper (rename = \"bar\")", "helper"]
+    );
+
+    let errors: Vec<_> = collector.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]
+        .to_string()
+        .starts_with("malformed `helper` attribute"));
+    // the error points at the attribute
+    assert_eq!(errors[0].span().start().line, 10);
+}
+
+#[test]
+fn test_variant_helper_attrs() {
+    let data: Variant = parse_quote!(
+        #[helper(rename = "bar")]
+        Bar(
+            #[helper]
+            u8,
+            u16
+        )
+    );
+
+    let mut collector = Collector::new();
+    let attrs = Context::new_by_ref(&mut collector, &data).helper_attrs("helper");
+    assert_eq!(attrs.len(), 1);
+
+    let fields: Vec<_> = data
+        .fields
+        .iter()
+        .map(|field| {
+            Context::new_by_ref(&mut collector, field)
+                .helper_attrs("helper")
+                .len()
+        })
+        .collect();
+    assert_eq!(fields, [1, 0]);
+    assert!(!collector.has_errors());
+}
+
+#[test]
+fn test_union_helper_attrs() {
+    let data: DeriveInput = parse_quote!(
+        union Foo {
+            #[helper]
+            a: u8,
+            b: u16,
+        }
+    );
+
+    let mut collector = Collector::new();
+    let attrs = Context::new_by_ref(&mut collector, &data).helper_attrs("helper");
+    assert_eq!(attrs.len(), 1);
+    assert!(!collector.has_errors());
+}
+
+fn spans(tokens: TokenStream) -> Vec<String> {
+    let mut spans = Vec::new();
+    for tt in tokens {
+        spans.push(format!("{:?}", tt.span()));
+        if let proc_macro2::TokenTree::Group(group) = tt {
+            spans.extend(self::spans(group.stream()));
+        }
+    }
+    spans
+}
+
+#[test]
+fn test_expand_respanned() {
+    let data: ItemStruct = syn::parse_str("struct Foo { a: u8 }").unwrap();
+    let span = data.ident.span();
+    let span_debug = format!("{:?}", span);
+
+    let mut collector = Collector::new();
+    Context::new_by_ref(&mut collector, &data).expand(&EchoExpand);
+    let default = spans(collector.finish());
+    assert!(default.iter().any(|s| *s != span_debug));
+
+    let mut collector = Collector::new();
+    Context::new_by_ref(&mut collector, &data).expand_respanned(span, &EchoExpand);
+    let output = collector.finish();
+    assert_eq!(output.to_string(), "struct Foo { a : u8 }");
+    let respanned = spans(output);
+    assert_eq!(respanned.len(), default.len());
+    assert!(respanned.iter().all(|s| *s == span_debug));
+}
+
+#[test]
+fn test_detach() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let ctx = Context::new_by_ref(&mut collector, &data);
+    assert_eq!(ctx.to_inner().as_ref(), Some(&data));
+
+    let mut ctx = ctx.detach();
+    assert!(ctx.modify(|data| data.ident = parse_quote!(BAR)));
+    ctx.expand(&ConstNameExpand);
+    assert_eq!(ctx.into_inner().unwrap().ident, "BAR");
+
+    assert_eq!(data.ident, "FOO");
+    assert_eq!(collector.finish().to_string(), "BAR");
+}
+
+struct NoGenericsLint;
+
+impl Lint<syn::Generics> for NoGenericsLint {
+    fn lint(&self, input: &syn::Generics, c: &mut Collector) {
+        if !input.params.is_empty() {
+            c.error_spanned(input, "generics are not supported");
+        }
+    }
+}
+
+#[test]
+fn test_enter() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo<T>(T);
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert_eq!(
+        ctx.enter(|input| &input.generics, |ctx| ctx.lint(&NoGenericsLint)),
+        Some(false)
+    );
+    // errors reported in the child context gate the parent
+    ctx.expand(&EchoExpand);
+    let (output, errors) = collector.into_parts();
+    assert!(output.is_empty());
+    assert_eq!(errors.len(), 1);
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::<DeriveInput>::new_empty(&mut collector);
+    let res = ctx.enter(
+        |_| -> &syn::Generics { panic!("the projection shouldn't be called") },
+        |ctx| ctx.lint(&NoGenericsLint),
+    );
+    assert_eq!(res, None);
+}
+
+#[test]
+fn test_lint_gating() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    assert_eq!(collector.error_count(), 2);
+
+    let mut collector = Collector::new();
+    collector.set_lint_gating(true);
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(ctx.lint(&NoOpLint));
+    assert!(!ctx.lint(&AlwaysErrorLint));
+    assert_eq!(ctx.lint_counted(&AlwaysErrorLint), None);
+    ctx.run(&LintPass(AlwaysErrorLint));
+    assert_eq!(collector.error_count(), 1);
+}
+
+struct RenameFold;
+
+impl syn::fold::Fold for RenameFold {
+    fn fold_ident(&mut self, ident: Ident) -> Ident {
+        if ident == "FOO" {
+            Ident::new("BAR", ident.span())
+        } else {
+            ident
+        }
+    }
+}
+
+#[test]
+fn test_take_data() {
+    use syn::fold::Fold;
+
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data.clone());
+    let taken = ctx.take_data().unwrap();
+    assert!(!ctx.has_data());
+    assert!(!ctx.is_poisoned());
+    ctx.put_data(RenameFold.fold_item_const(taken));
+    ctx.expand(&ConstNameExpand);
+    assert_eq!(collector.finish().to_string(), "BAR");
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert_eq!(ctx.take_data(), None);
+    assert!(ctx.has_data());
+}
+
+#[test]
+fn test_lint_fn() {
+    let data: DeriveInput = parse_quote!(
+        enum Foo {
+            Bar,
+            Baz,
+        }
+    );
+
+    let max_variants = 1;
+    let max_variants_lint = lint_fn(|input: &DeriveInput, c| {
+        if let Data::Enum(e) = &input.data {
+            if e.variants.len() > max_variants {
+                c.error_spanned(&input.ident, "too many variants");
+            }
+        }
+    });
+
+    let no_op_lint = lint_fn(|_: &DeriveInput, _| {});
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(ctx.lint(&no_op_lint));
+    assert!(!ctx.lint(&max_variants_lint));
+    assert!(!ctx.lint_all(&[&no_op_lint, &max_variants_lint]));
+    ctx.expand(&DeriveIdentExpand);
+
+    let boxed: Box<dyn Lint<DeriveInput>> = Box::new(max_variants_lint);
+    assert!(!ctx.lint(&boxed));
+
+    let errors: Vec<_> = collector.errors().map(|e| e.to_string()).collect();
+    assert_eq!(errors, ["too many variants"; 3]);
+    assert!(collector.into_parts().0.is_empty());
+}
+
+#[test]
+fn test_lint_references_and_boxes() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let errors_lint = ErrorsLint(2);
+    let boxed: Box<ErrorsLint> = Box::new(ErrorsLint(1));
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.lint(&&NoOpLint));
+    assert!(!ctx.lint(&&errors_lint));
+    assert!(!ctx.lint(&boxed));
+    assert!(!ctx.lint(&&boxed));
+    assert_eq!(collector.error_count(), 4);
+}
+
+struct EnsureEnumLint;
+
+impl Lint<DeriveInput> for EnsureEnumLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if !matches!(input.data, Data::Enum(_)) {
+            c.error_spanned(input, "expected an enum");
+        }
+    }
+}
+
+struct UnitVariantsLint;
+
+impl Lint<DeriveInput> for UnitVariantsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if let Data::Enum(e) = &input.data {
+            for variant in e.variants.iter() {
+                UnitVariantLint.lint(variant, c);
+            }
+        }
+    }
+}
+
+struct NoGenericParamsLint;
+
+impl Lint<DeriveInput> for NoGenericParamsLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        NoGenericsLint.lint(&input.generics, c);
+    }
+}
+
+#[test]
+fn test_lint_and() {
+    let lint = EnsureEnumLint
+        .and(UnitVariantsLint)
+        .and(NoGenericParamsLint);
+
+    let data: DeriveInput = parse_quote!(
+        enum Foo<T> {
+            Bar(T),
+            Baz,
+        }
+    );
+    let mut collector = Collector::new();
+    assert!(!Context::new_by_ref(&mut collector, &data).lint(&lint));
+    let errors: Vec<_> = collector.errors().map(|e| e.to_string()).collect();
+    assert_eq!(
+        errors,
+        ["expected a unit variant", "generics are not supported"]
+    );
+
+    let data: DeriveInput = parse_quote!(
+        enum Foo {
+            Bar,
+        }
+    );
+    let mut collector = Collector::new();
+    assert!(Context::new_by_ref(&mut collector, &data).lint(&lint));
+}
+
+#[test]
+fn test_lint_or() {
+    let enum_lint = lint_fn(|input: &DeriveInput, c| {
+        EnsureEnumLint.lint(input, c);
+        c.warning(Error::new(Span::call_site(), "enum warning"));
+    });
+    let lint = enum_lint.or(NoGenericParamsLint);
+
+    // pass, the second lint doesn't run
+    let data: DeriveInput = parse_quote!(
+        enum Foo<T> {
+            Bar(T),
+        }
+    );
+    let mut collector = Collector::new();
+    assert!(Context::new_by_ref(&mut collector, &data).lint(&lint));
+    assert!(collector.finish().to_string().contains("enum warning"));
+
+    // fail, pass
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+    let mut collector = Collector::new();
+    assert!(Context::new_by_ref(&mut collector, &data).lint(&lint));
+    assert!(!collector.finish().to_string().contains("enum warning"));
+
+    // fail, fail
+    let data: DeriveInput = parse_quote!(
+        struct Foo<T>(T);
+    );
+    let mut collector = Collector::new();
+    assert!(!Context::new_by_ref(&mut collector, &data).lint(&lint));
+    let errors: Vec<_> = collector.errors().map(|e| e.to_string()).collect();
+    assert_eq!(errors, ["expected an enum", "generics are not supported"]);
+}
+
+#[test]
+fn test_lint_or_captured() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let captured_name_lint = lint_fn(|input: &ItemConst, c| {
+        if c.captured::<ConstName>() != Some(&input.ident) {
+            c.error_spanned(&input.ident, "name wasn't captured");
+        }
+    });
+    let lint = ErrorsLint(1).or(captured_name_lint);
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(ctx.capture_into::<ConstName, _>(&ConstNameExpand));
+    assert!(ctx.lint(&lint));
+    assert!(!collector.has_errors());
+}
+
+#[test]
+fn test_lint_focus() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo<T>(T);
+    );
+
+    let mut collector = Collector::new();
+    let lint = NoGenericsLint.focus(|input: &DeriveInput| &input.generics);
+    assert!(!Context::new_by_ref(&mut collector, &data).lint(&lint));
+    assert_eq!(collector.error_count(), 1);
+}
+
+#[test]
+fn test_lint_try_focus() {
+    fn where_clause(input: &DeriveInput) -> Option<&syn::WhereClause> {
+        input.generics.where_clause.as_ref()
+    }
+    let not_empty = lint_fn(|input: &syn::WhereClause, c| {
+        if input.predicates.is_empty() {
+            c.error_spanned(input, "empty where clause");
+        }
+    });
+
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(ctx.lint(&not_empty.try_focus(where_clause)));
+    assert!(!ctx.lint(
+        &not_empty.try_focus_or(where_clause, |input: &DeriveInput| {
+            Error::new_spanned(&input.ident, "missing where clause")
+        })
+    ));
+
+    let data: DeriveInput = syn::parse_str("struct Foo where;").unwrap();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(!ctx.lint(&not_empty.try_focus(where_clause)));
+
+    let errors: Vec<_> = collector.errors().map(|e| e.to_string()).collect();
+    assert_eq!(errors, ["missing where clause", "empty where clause"]);
+}
+
+#[test]
+fn test_tuple_lint() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(ctx.lint(&(NoOpLint, NoOpLint)));
+    assert!(!ctx.lint(&(AlwaysWarningLint, AlwaysErrorLint, AlwaysWarningLint)));
+    assert_eq!(collector.error_count(), 1);
+    assert_eq!(
+        collector
+            .finish()
+            .to_string()
+            .matches("some warning message")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_tuple_expand() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    ctx.expand(&(ConstNameExpand, EchoExpand));
+    assert_eq!(
+        ctx.capture(&(NoOpExpand, NoOpExpand))
+            .map(|t| t.to_string()),
+        None
+    );
+    assert_eq!(
+        ctx.capture(&(AlwaysErrorExpand, PanickingExpand))
+            .map(|t| t.to_string()),
+        None
+    );
+    let (output, errors) = collector.into_parts();
+    assert_eq!(output.to_string(), "FOO const FOO : bool = true ;");
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_vec_lint() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let lints: Vec<Box<dyn Lint<ItemConst>>> = vec![
+        Box::new(NoOpLint),
+        Box::new(AlwaysErrorLint),
+        Box::new(AlwaysWarningLint),
+        Box::new(lint_fn(|_: &ItemConst, c| {
+            c.error(Error::new(Span::call_site(), "closure error"))
+        })),
+    ];
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(!ctx.lint(&lints));
+    assert_eq!(ctx.lint_counted(&lints[2..]), Some(1));
+
+    let errors: Vec<_> = collector.errors().map(|e| e.to_string()).collect();
+    assert_eq!(
+        errors,
+        ["some error message", "closure error", "closure error"]
+    );
+    assert!(collector
+        .finish()
+        .to_string()
+        .contains("some warning message"));
+}
+
+/// checks if the input has a `#[from_str(...)]` attribute containing the key
+fn has_from_str_key(input: &DeriveInput, key: &str) -> bool {
+    input
+        .attrs
+        .iter()
+        .any(|attr| attr.path.is_ident("from_str") && attr.tokens.to_string().contains(key))
+}
+
+fn unique_case_insensitive(input: &DeriveInput, c: &mut Collector) {
+    if let syn::Data::Enum(e) = &input.data {
+        let mut names = Vec::new();
+        for variant in e.variants.iter() {
+            let name = variant.ident.to_string().to_lowercase();
+            if names.contains(&name) {
+                c.error_spanned(
+                    &variant.ident,
+                    "variant names must be unique case-insensitively",
+                );
+            }
+            names.push(name);
+        }
+    }
+}
+
+#[test]
+fn test_only_if() {
+    let lint = only_if(
+        |input: &DeriveInput| has_from_str_key(input, "case_insensitive"),
+        lint_fn(unique_case_insensitive),
+    );
+
+    let mut collector = Collector::new();
+    let input: DeriveInput = parse_quote!(
+        enum Foo {
+            Foo,
+            FOO,
+        }
+    );
+    assert!(Context::new(&mut collector, input).lint(&lint));
+    assert!(!collector.has_errors());
+
+    let input: DeriveInput = parse_quote!(
+        #[from_str(case_insensitive)]
+        enum Foo {
+            Foo,
+            FOO,
+        }
+    );
+    assert!(!Context::new(&mut collector, input).lint(&lint));
+    assert_eq!(collector.error_count(), 1);
+}
+
+#[test]
+fn test_unless() {
+    let lint = unless(
+        |input: &DeriveInput| has_from_str_key(input, "case_sensitive"),
+        lint_fn(unique_case_insensitive),
+    );
+
+    let mut collector = Collector::new();
+    let input: DeriveInput = parse_quote!(
+        #[from_str(case_sensitive)]
+        enum Foo {
+            Foo,
+            FOO,
+        }
+    );
+    assert!(Context::new(&mut collector, input).lint(&lint));
+
+    let input: DeriveInput = parse_quote!(
+        enum Foo {
+            Foo,
+            FOO,
+        }
+    );
+    assert!(!Context::new(&mut collector, input).lint(&lint));
+    assert_eq!(collector.error_count(), 1);
+}
+
+fn cfg_input() -> DeriveInput {
+    parse_quote!(
+        enum Foo {
+            Foo,
+            #[cfg(feature = "bar")]
+            Bar(u8),
+        }
+    )
+}
+
+#[test]
+fn test_cfg_aware_lint() {
+    let mut collector = Collector::new();
+    assert!(!Context::new(&mut collector, cfg_input()).lint(&EnsureUnitVariants::new()));
+
+    let mut collector = Collector::new();
+    let lint = CfgAware(EnsureUnitVariants::new());
+    assert!(Context::new(&mut collector, cfg_input()).lint(&lint));
+}
+
+struct NameExpand;
+
+impl Expand<DeriveInput> for NameExpand {
+    type Output = TokenStream;
+
+    fn expand(&self, input: &DeriveInput, _: &mut Collector) -> Option<Self::Output> {
+        let e = match &input.data {
+            syn::Data::Enum(e) => e,
+            _ => return None,
+        };
+        let arms = e.variants.iter().map(|variant| {
+            let ident = &variant.ident;
+            let name = ident.to_string();
+            with_cfg(&variant.attrs, quote!(Self::#ident { .. } => #name,))
+        });
+        Some(quote!(match self { #(#arms)* }))
+    }
+}
+
+#[test]
+fn test_with_cfg() {
+    let mut collector = Collector::new();
+    Context::new(&mut collector, cfg_input()).expand(&NameExpand);
+
+    let expected = quote!(match self {
+        Self::Foo { .. } => "Foo",
+        #[cfg(feature = "bar")]
+        Self::Bar { .. } => "Bar",
+    });
+    assert_eq!(collector.expect_success().to_string(), expected.to_string());
+}
+
+fn no_references(field: &syn::Field, c: &mut Collector) {
+    if let syn::Type::Reference(_) = &field.ty {
+        c.error_spanned(&field.ty, "references are not supported");
+    }
+}
+
+/// the start columns of the errors reported by the lint
+fn error_columns(lint: &impl Lint<DeriveInput>, input: &str) -> Vec<usize> {
+    common::lint(lint, input)
+        .into_iter()
+        .map(|(_, column)| column)
+        .collect()
+}
+
+#[test]
+fn test_for_each_field_lint() {
+    let lint = ForEachField::new(lint_fn(no_references));
+    assert_eq!(
+        error_columns(&lint, "struct Foo<'a> { a: u8, b: &'a u8, c: &'a u8 }"),
+        [27, 38]
+    );
+    assert_eq!(
+        error_columns(
+            &lint,
+            "enum Foo<'a> { A(&'a u8), B { b: u8 }, C(u8, &'a u8) }"
+        ),
+        [17, 45]
+    );
+    assert_eq!(error_columns(&lint, "union Foo<'a> { a: &'a u8 }"), [19]);
+}
+
+#[test]
+fn test_for_each_field_lint_skip() {
+    let lint = ForEachField::new(lint_fn(no_references)).skip_unnamed();
+    assert_eq!(
+        error_columns(&lint, "enum Foo<'a> { A(&'a u8), B { b: &'a u8 } }"),
+        [33]
+    );
+
+    let lint = ForEachField::new(lint_fn(no_references)).skip_unions();
+    assert!(error_columns(&lint, "union Foo<'a> { a: &'a u8 }").is_empty());
+}
+
+fn literal_discriminant(variant: &syn::Variant, c: &mut Collector) {
+    if let Some((_, discriminant)) = &variant.discriminant {
+        if !matches!(discriminant, syn::Expr::Lit(_)) {
+            c.error_spanned(discriminant, "expected a literal");
+        }
+    }
+}
+
+#[test]
+fn test_for_each_variant_lint() {
+    let lint = ForEachVariant::new(lint_fn(literal_discriminant));
+    assert_eq!(
+        error_columns(&lint, "enum Foo { A = 1 + 1, B = 2, C = -3 }"),
+        [15, 33]
+    );
+    assert_eq!(error_columns(&lint, "pub struct Foo;"), [4]);
+
+    let lint = ForEachVariant::new(lint_fn(literal_discriminant)).skip_non_enums();
+    assert!(error_columns(&lint, "pub struct Foo;").is_empty());
+}
+
+#[test]
+fn test_for_each_variant_lint_compose() {
+    let input = "enum Foo { A = 1 + 1, B = 2, C = -3 }";
+
+    let lint = only_if(
+        |input: &DeriveInput| input.attrs.is_empty(),
+        ForEachVariant::new(lint_fn(literal_discriminant)),
+    );
+    assert!(error_columns(&lint, &format!("#[repr(u8)] {}", input)).is_empty());
+    assert_eq!(error_columns(&lint, input).len(), 2);
+
+    let lint = ForEachVariant::new(lint_fn(literal_discriminant)).and(ForEachVariant::new(
+        lint_fn(|variant: &syn::Variant, c| {
+            if variant.ident == "B" {
+                c.error_spanned(&variant.ident, "unexpected variant");
+            }
+        }),
+    ));
+    assert_eq!(error_columns(&lint, input), [15, 33, 22]);
+}
+
+struct LogStep(
+    &'static str,
+    std::rc::Rc<std::cell::RefCell<Vec<&'static str>>>,
+);
+
+impl Lint<ItemConst> for LogStep {
+    fn lint(&self, _: &ItemConst, _: &mut Collector) {
+        self.1.borrow_mut().push(self.0);
+    }
+}
+
+impl Expand<ItemConst> for LogStep {
+    type Output = TokenStream;
+
+    fn expand(&self, _: &ItemConst, _: &mut Collector) -> Option<Self::Output> {
+        self.1.borrow_mut().push(self.0);
+        None
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    let log = std::rc::Rc::default();
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    pipeline! { ctx =>
+        lint LogStep("a", std::rc::Rc::clone(&log)),
+        expand LogStep("b", std::rc::Rc::clone(&log)),
+        lint LogStep("c", std::rc::Rc::clone(&log)),
+        expand EchoExpand,
+        expand LogStep("d", std::rc::Rc::clone(&log))
+    }
+
+    assert_eq!(*log.borrow(), ["a", "b", "c", "d"]);
+    assert!(collector.expect_success().to_string().contains("const FOO"));
+}
+
+#[test]
+fn test_pipeline_stops_after_error() {
+    let log = std::rc::Rc::default();
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut ctx = OwnedContext::new(Collector::new(), data);
+    pipeline! { ctx =>
+        lint AlwaysErrorLint,
+        lint LogStep("a", std::rc::Rc::clone(&log)),
+        expand LogStep("b", std::rc::Rc::clone(&log)),
+    }
+
+    assert_eq!(*log.borrow(), ["a"]);
+    ctx.finish_result().unwrap_err();
+}
+
+/// the name of the const, rejects names starting with an underscore
+struct ConstNameAnalyze;
+
+impl Analyze<ItemConst> for ConstNameAnalyze {
+    type Output = Ident;
+
+    fn analyze(&self, input: &ItemConst, c: &mut Collector) -> Option<Self::Output> {
+        if input.ident.to_string().starts_with('_') {
+            c.error_spanned(&input.ident, "names must not start with an underscore");
+            return None;
+        }
+        Some(input.ident.clone())
+    }
+}
+
+#[test]
+fn test_analyze_into() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut ctx = OwnedContext::new(Collector::new(), data);
+    assert!(ctx.analyze_into::<ConstName, _>(&ConstNameAnalyze));
+    ctx.expand(&WithCaptured::<ConstName, _>::new(ConstNameStructExpand));
+    ctx.expand(&WithCaptured::<ConstName, _>::new(ConstNameStructExpand));
+
+    let output = ctx.finish_result().unwrap().to_string();
+    assert_eq!(output, "struct FOO ; struct FOO ;");
+}
+
+#[test]
+fn test_analyze_after_error() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&AlwaysErrorLint);
+    assert!(ctx.capture(&ConstNameExpand).is_none());
+    assert_eq!(ctx.analyze(&ConstNameAnalyze).unwrap(), "FOO");
+    collector.expect_errors(1);
+}
+
+#[test]
+fn test_analyze_failure_poisons_expands() {
+    let data: ItemConst = parse_quote!(
+        const _FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(!ctx.analyze_into::<ConstName, _>(&ConstNameAnalyze));
+    ctx.expand(&WithCaptured::<ConstName, _>::new(ConstNameStructExpand));
+    ctx.expand(&PanickingExpand);
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(
+        errors[0].to_string(),
+        "names must not start with an underscore"
+    );
+}
+
+/// only compiles if `Lint` and `Expand` can be used as trait objects
+#[allow(dead_code)]
+fn assert_object_safe(
+    _: &dyn Lint<DeriveInput>,
+    _: &dyn Expand<DeriveInput, Output = TokenStream>,
+    _: std::sync::Arc<dyn Lint<DeriveInput> + Send + Sync>,
+) {
+}
+
+#[test]
+fn test_lint_forwarding_impls() {
+    use std::{rc::Rc, sync::Arc};
+
+    let boxed: Box<dyn Lint<DeriveInput>> = Box::new(EnsureEnumLint);
+    let by_ref: &dyn Lint<DeriveInput> = &EnsureEnumLint;
+    let arc_dyn: Arc<dyn Lint<DeriveInput> + Send + Sync> = Arc::new(EnsureEnumLint);
+    let rc_dyn: Rc<dyn Lint<DeriveInput>> = Rc::new(EnsureEnumLint);
+
+    let mut collector = Collector::new();
+    let input: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+    let mut ctx = Context::new(&mut collector, input);
+    assert!(!ctx.lint(&boxed));
+    assert!(!ctx.lint(&by_ref));
+    assert!(!ctx.lint(&arc_dyn));
+    assert!(!ctx.lint(&rc_dyn));
+    assert!(!ctx.lint(&Arc::new(EnsureEnumLint)));
+    assert!(!ctx.lint(&Rc::new(EnsureEnumLint)));
+    assert_eq!(collector.error_count(), 6);
+}
+
+#[test]
+fn test_expand_forwarding_impls() {
+    use std::{rc::Rc, sync::Arc};
+
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&Arc::new(EchoExpand));
+    ctx.expand(&Rc::new(EchoExpand));
+    let boxed: Box<dyn Expand<ItemConst, Output = ItemConst>> = Box::new(EchoExpand);
+    ctx.expand(&Rc::new(boxed));
+
+    let output = collector.expect_success().to_string();
+    assert_eq!(output.matches("const FOO").count(), 3);
+}
+
+#[test]
+fn test_expect_error() {
+    let lint = ExpectError::new(EnsureEnumLint, "expected an enum");
+
+    let mut collector = Collector::new();
+    let input: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+    assert!(Context::new(&mut collector, input).lint(&lint));
+    collector.expect_success();
+}
+
+#[test]
+fn test_expect_error_unexpected_pass() {
+    let lint = ExpectError::new(EnsureEnumLint, "expected an enum");
+
+    let mut collector = Collector::new();
+    let input: DeriveInput = parse_quote!(
+        enum Foo {}
+    );
+    assert!(!Context::new(&mut collector, input).lint(&lint));
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(
+        errors[0].to_string(),
+        "expected the lint to report an error containing `expected an enum`, but it passed"
+    );
+}
+
+#[test]
+fn test_expect_error_wrong_message() {
+    let lint = ExpectError::new(EnsureEnumLint.and(NoGenericParamsLint), "expected a struct");
+
+    let mut collector = Collector::new();
+    let input: DeriveInput = parse_quote!(
+        struct Foo<T>(T);
+    );
+    assert!(!Context::new(&mut collector, input).lint(&lint));
+
+    let errors = collector.expect_errors(1);
+    assert_eq!(
+        errors[0].to_string(),
+        "expected the lint to report an error containing `expected a struct`, found `expected an enum`, `generics are not supported`"
+    );
+}
+
+#[test]
+fn test_expect_error_captured() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let captured_name_lint = lint_fn(|input: &ItemConst, c| {
+        if let Some(name) = c.captured::<ConstName>().cloned() {
+            c.error_spanned(&input.ident, format!("`{}` was captured", name));
+        }
+    });
+    let lint = ExpectError::new(captured_name_lint, "`FOO` was captured");
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new_by_ref(&mut collector, &data);
+    assert!(ctx.capture_into::<ConstName, _>(&ConstNameExpand));
+    assert!(ctx.lint(&lint));
+    collector.expect_success();
+}
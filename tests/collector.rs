@@ -0,0 +1,1013 @@
+use macro_compose::{
+    Collector, Context, EchoExpand, ErrorEntry, Expand, Lint, Nothing, SharedCollector, Slot,
+};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Error, ItemConst};
+
+#[test]
+fn test_finish_result_ok() {
+    let collector = Collector::new();
+    let output = collector.finish_result().unwrap();
+    assert!(output.is_empty());
+}
+
+#[test]
+fn test_finish_result_err() {
+    let mut collector = Collector::new();
+    collector.error(Error::new(Span::call_site(), "first"));
+    collector.error(Error::new(Span::call_site(), "second"));
+
+    let errors = collector.finish_result().unwrap_err();
+    let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(messages, ["first", "second"]);
+}
+
+#[test]
+fn test_finish_error_order() {
+    let first = Error::new(Span::call_site(), "first");
+    let second = Error::new(Span::call_site(), "second");
+
+    let mut collector = Collector::new();
+    collector.error(first.clone());
+    collector.error(second.clone());
+
+    let mut expected = TokenStream::new();
+    expected.extend(first.to_compile_error());
+    expected.extend(second.to_compile_error());
+    assert_eq!(collector.finish().to_string(), expected.to_string());
+}
+
+#[test]
+fn test_finish_result_warning() {
+    let mut collector = Collector::new();
+    collector.warning(Error::new(Span::call_site(), "some warning"));
+
+    let output = collector.finish_result().unwrap();
+    assert!(output.to_string().contains("some warning"));
+}
+
+#[test]
+fn test_finish_order() {
+    let mut collector = Collector::new();
+    collector.warning(Error::new(Span::call_site(), "some warning"));
+    collector.append(quote!(
+        struct Foo;
+    ));
+    collector.error(Error::new(Span::call_site(), "some error"));
+
+    let output = collector.finish().to_string();
+    let error = output.find("some error").unwrap();
+    let warning = output.find("some warning").unwrap();
+    let item = output.find("struct Foo").unwrap();
+    assert!(error < warning && warning < item);
+}
+
+#[test]
+fn test_finish_split() {
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct Foo;
+    ));
+    collector.error(Error::new(Span::call_site(), "some error"));
+    collector.append_unchecked(quote!(
+        struct Bar;
+    ));
+
+    let (items, diagnostics) = collector.finish_split();
+    assert_eq!(items.to_string(), "struct Foo ; struct Bar ;");
+    assert_eq!(
+        diagnostics.to_string(),
+        Error::new(Span::call_site(), "some error")
+            .to_compile_error()
+            .to_string()
+    );
+}
+
+#[test]
+fn test_errors() {
+    let mut collector = Collector::new();
+    assert_eq!(collector.errors().len(), 0);
+
+    let span = Span::call_site();
+    collector.error(Error::new(span, "first"));
+    collector.error(Error::new(span, "second"));
+
+    let messages: Vec<_> = collector.errors().map(ToString::to_string).collect();
+    assert_eq!(messages, ["first", "second"]);
+    assert!(collector
+        .errors()
+        .all(|e| format!("{:?}", e.span()) == format!("{:?}", span)));
+}
+
+#[test]
+fn test_merge() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut clean = Collector::new();
+    Context::new(&mut clean, data).expand(&EchoExpand);
+
+    let mut erroring = Collector::new();
+    erroring.error(Error::new(Span::call_site(), "some error"));
+
+    clean.merge(Collector::new());
+    assert!(!clean.has_errors());
+
+    clean.merge(erroring);
+    assert!(clean.has_errors());
+    assert_eq!(clean.errors().len(), 1);
+
+    let (items, diagnostics) = clean.finish_split();
+    assert_eq!(items.to_string(), "const FOO : bool = true ;");
+    assert!(diagnostics.to_string().contains("some error"));
+}
+
+#[test]
+fn test_append() {
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct Foo;
+    ));
+    collector.error(Error::new(Span::call_site(), "some error"));
+    collector.append(quote!(
+        struct Bar;
+    ));
+
+    let output = collector.finish().to_string();
+    assert!(output.contains("struct Foo"));
+    assert!(!output.contains("struct Bar"));
+}
+
+#[test]
+fn test_append_unchecked() {
+    let mut collector = Collector::new();
+    collector.error(Error::new(Span::call_site(), "some error"));
+    collector.append_unchecked(quote!(
+        struct Foo;
+    ));
+
+    let output = collector.finish().to_string();
+    assert!(output.contains("some error"));
+    assert!(output.contains("struct Foo"));
+}
+
+#[test]
+fn test_max_errors() {
+    let data: DeriveInput = parse_quote!(
+        enum Foo {
+            A,
+            B,
+            C,
+            D,
+            E,
+        }
+    );
+
+    let mut collector = Collector::new();
+    collector.set_max_errors(2);
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&ErrorPerVariantLint);
+    assert_eq!(collector.error_count(), 5);
+
+    let output = collector.finish().to_string();
+    assert_eq!(output.matches("compile_error").count(), 3);
+    assert!(output.contains("variant A"));
+    assert!(output.contains("variant B"));
+    assert!(!output.contains("variant C"));
+    assert!(output.contains("... and 3 more errors"));
+}
+
+#[test]
+fn test_max_errors_not_reached() {
+    let data: DeriveInput = parse_quote!(
+        enum Foo {
+            A,
+            B,
+        }
+    );
+
+    let mut collector = Collector::new();
+    collector.set_max_errors(2);
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&ErrorPerVariantLint);
+
+    let output = collector.finish().to_string();
+    assert_eq!(output.matches("compile_error").count(), 2);
+    assert!(!output.contains("more error"));
+}
+
+struct ErrorPerVariantLint;
+
+impl Lint<DeriveInput> for ErrorPerVariantLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if let Data::Enum(e) = &input.data {
+            for variant in e.variants.iter() {
+                let message = format!("variant {}", variant.ident);
+                c.error(Error::new_spanned(variant, message));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_error_with_help() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    collector.error_with_help(
+        Error::new_spanned(&data, "unexpected const"),
+        "use a static instead",
+    );
+    collector.error_with_note(
+        Error::new_spanned(&data.ty, "unexpected type"),
+        "only integers are supported",
+    );
+
+    let messages: Vec<_> = collector.errors().map(ToString::to_string).collect();
+    assert_eq!(messages, ["unexpected const", "unexpected type"]);
+
+    let errors = collector.finish_result().unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "unexpected const\nhelp: use a static instead"
+    );
+    assert_eq!(
+        errors[1].to_string(),
+        "unexpected type\nnote: only integers are supported"
+    );
+}
+
+#[test]
+fn test_error_with_help_rendering() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    collector.error_with_help(Error::new_spanned(&data, "unexpected const"), "help text");
+
+    let expected = Error::new_spanned(&data, "unexpected const\nhelp: help text");
+    assert_eq!(
+        collector.finish().to_string(),
+        expected.to_compile_error().to_string()
+    );
+}
+
+#[test]
+fn test_finish_with_fallback() {
+    let fallback = quote!(
+        impl ::core::str::FromStr for Foo {
+            type Err = ();
+
+            fn from_str(_: &str) -> ::core::result::Result<Self, Self::Err> {
+                unimplemented!()
+            }
+        }
+    );
+
+    let data: DeriveInput = parse_quote!(
+        struct Foo;
+    );
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(!ctx.lint(&EnsureEnumLint));
+    let output = collector.finish_with_fallback(fallback.clone()).to_string();
+    assert!(output.contains("expected an enum"));
+    assert!(output.contains("unimplemented"));
+
+    let data: DeriveInput = parse_quote!(
+        enum Foo {}
+    );
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert!(ctx.lint(&EnsureEnumLint));
+    let output = collector.finish_with_fallback(fallback);
+    assert!(output.is_empty());
+}
+
+struct EnsureEnumLint;
+
+impl Lint<DeriveInput> for EnsureEnumLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if !matches!(input.data, Data::Enum(_)) {
+            c.error(Error::new_spanned(input, "expected an enum"));
+        }
+    }
+}
+
+#[test]
+fn test_name() {
+    let mut collector = Collector::with_name("#[derive(FromStr)]");
+    collector.error(Error::new(Span::call_site(), "expected an enum"));
+
+    let messages: Vec<_> = collector.errors().map(ToString::to_string).collect();
+    assert_eq!(messages, ["expected an enum"]);
+
+    let expected = Error::new(Span::call_site(), "#[derive(FromStr)]: expected an enum");
+    assert_eq!(
+        collector.finish().to_string(),
+        expected.to_compile_error().to_string()
+    );
+}
+
+#[test]
+fn test_empty_name() {
+    let error = Error::new(Span::call_site(), "expected an enum");
+
+    let mut collector = Collector::with_name("");
+    collector.error(error.clone());
+    assert_eq!(
+        collector.finish().to_string(),
+        error.to_compile_error().to_string()
+    );
+}
+
+#[test]
+fn test_into_parts() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::with_name("foo");
+    Context::new(&mut collector, data).expand(&EchoExpand);
+    collector.error(Error::new(Span::call_site(), "some error"));
+
+    let (output, errors) = collector.into_parts();
+    assert_eq!(output.to_string(), "const FOO : bool = true ;");
+    let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(messages, ["foo: some error"]);
+}
+
+#[cfg(feature = "nightly-diagnostics")]
+#[test]
+fn test_nightly_diagnostics_outside_proc_macro() {
+    // outside of a proc-macro the diagnostic api is not available, so errors are still rendered as tokens
+    let error = Error::new(Span::call_site(), "some error");
+    let mut collector = Collector::new();
+    collector.error(error.clone());
+    assert_eq!(
+        collector.finish().to_string(),
+        error.to_compile_error().to_string()
+    );
+}
+
+#[test]
+fn test_lint_only() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new_lint_only();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&EchoExpand);
+    assert!(ctx.capture(&EchoExpand).is_some());
+    collector.append(quote!(
+        struct Foo;
+    ));
+    let (items, diagnostics) = collector.finish_split();
+    assert!(items.is_empty());
+    assert!(diagnostics.is_empty());
+
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+    let mut collector = Collector::new_lint_only();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&EchoExpand);
+    ctx.expand(&ErroringExpand);
+    assert_eq!(collector.error_count(), 1);
+    let output = collector.finish().to_string();
+    assert!(output.contains("some error"));
+    assert!(!output.contains("const FOO"));
+}
+
+struct ErroringExpand;
+
+impl Expand<ItemConst> for ErroringExpand {
+    type Output = ItemConst;
+
+    fn expand(&self, input: &ItemConst, c: &mut Collector) -> Option<Self::Output> {
+        c.error(Error::new_spanned(input, "some error"));
+        None
+    }
+}
+
+#[test]
+fn test_error_with_context() {
+    let data: ItemConst = syn::parse_str("const FOO: bool = true;").unwrap();
+
+    let mut error = Error::new_spanned(&data.ident, "expected a string literal");
+    error.combine(Error::new_spanned(&data.expr, "unexpected expression"));
+
+    let mut collector = Collector::new();
+    collector.error_with_context(error.clone(), "invalid #[from_str(rename)] value");
+
+    let reported = collector.errors().next().unwrap();
+    let messages: Vec<_> = reported.clone().into_iter().collect();
+    let original: Vec<_> = error.into_iter().collect();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(
+        messages[0].to_string(),
+        "invalid #[from_str(rename)] value: expected a string literal"
+    );
+    assert_eq!(
+        messages[1].to_string(),
+        "invalid #[from_str(rename)] value: unexpected expression"
+    );
+    for (message, original) in messages.iter().zip(original.iter()) {
+        assert_eq!(message.span().start(), original.span().start());
+        assert_eq!(message.span().end(), original.span().end());
+    }
+    assert_ne!(messages[0].span().start(), messages[1].span().start());
+}
+
+#[test]
+fn test_slots() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let first = collector.reserve();
+    let second = collector.reserve();
+    collector.append(quote!(
+        struct Last;
+    ));
+    let third = collector.reserve();
+
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&FillExpand(
+        third,
+        quote!(
+            struct C;
+        ),
+    ));
+    ctx.expand(&FillExpand(
+        first,
+        quote!(
+            struct A;
+        ),
+    ));
+    ctx.expand(&FillExpand(
+        second,
+        quote!(
+            struct B;
+        ),
+    ));
+
+    assert_eq!(
+        collector.finish().to_string(),
+        "struct A ; struct B ; struct Last ; struct C ;"
+    );
+}
+
+#[test]
+fn test_prepend() {
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct C;
+    ));
+    collector.prepend(quote!(
+        struct B;
+    ));
+    collector.prepend(quote!(
+        struct A;
+    ));
+    assert_eq!(
+        collector.finish().to_string(),
+        "struct A ; struct B ; struct C ;"
+    );
+}
+
+#[test]
+fn test_prepend_with_sections() {
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct C;
+    ));
+    collector.append_to(
+        "impls",
+        quote!(
+            struct B;
+        ),
+    );
+    collector.prepend(quote!(
+        struct A;
+    ));
+    assert_eq!(
+        collector.finish().to_string(),
+        "struct A ; struct B ; struct C ;"
+    );
+}
+
+struct FillExpand(Slot, TokenStream);
+
+impl Expand<ItemConst> for FillExpand {
+    type Output = Nothing;
+
+    fn expand(&self, _: &ItemConst, c: &mut Collector) -> Option<Self::Output> {
+        c.fill(self.0, self.1.clone());
+        None
+    }
+}
+
+#[test]
+fn test_rollback() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct Foo;
+    ));
+    let checkpoint = collector.checkpoint();
+
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand(&EchoExpand);
+    ctx.expand(&ErroringExpand);
+    collector.warning(Error::new(Span::call_site(), "some warning"));
+    assert!(collector.has_errors());
+
+    collector.rollback(checkpoint);
+    assert!(!collector.has_errors());
+    assert_eq!(collector.finish().to_string(), "struct Foo ;");
+}
+
+#[test]
+fn test_rollback_removes_slots() {
+    let mut collector = Collector::new();
+    let kept = collector.reserve();
+    let checkpoint = collector.checkpoint();
+    let removed = collector.reserve();
+    collector.rollback(checkpoint);
+
+    collector.fill(
+        removed,
+        quote!(
+            struct Removed;
+        ),
+    );
+    collector.append(quote!(
+        struct B;
+    ));
+    collector.fill(
+        removed,
+        quote!(
+            struct Removed;
+        ),
+    );
+    collector.fill(
+        kept,
+        quote!(
+            struct A;
+        ),
+    );
+    assert_eq!(collector.finish().to_string(), "struct A ; struct B ;");
+}
+
+#[test]
+fn test_error_spanned() {
+    let data: ItemConst = syn::parse_str("const FOO: bool = true;").unwrap();
+
+    let mut collector = Collector::new();
+    collector.error_spanned(&data.ty, "unexpected type");
+    collector.error_at(data.ident.span(), "unexpected name");
+
+    let expected = [
+        Error::new_spanned(&data.ty, "unexpected type"),
+        Error::new(data.ident.span(), "unexpected name"),
+    ];
+    for (error, expected) in collector.errors().zip(expected.iter()) {
+        assert_eq!(error.to_string(), expected.to_string());
+        assert_eq!(error.span().start(), expected.span().start());
+        assert_eq!(error.span().end(), expected.span().end());
+    }
+
+    let expected: TokenStream = expected.iter().map(Error::to_compile_error).collect();
+    assert_eq!(collector.finish().to_string(), expected.to_string());
+}
+
+#[test]
+fn test_shared_collector() {
+    let shared = SharedCollector::new();
+    let other = shared.clone();
+
+    shared.with(|c| {
+        c.append(quote!(
+            struct Foo;
+        ))
+    });
+    other.error(Error::new(Span::call_site(), "some error"));
+    assert!(shared.has_errors());
+    assert_eq!(shared.error_count(), 1);
+
+    let shared = match shared.try_unwrap() {
+        Ok(_) => panic!("there is another clone"),
+        Err(shared) => shared,
+    };
+    drop(other);
+    let collector = shared.try_unwrap().ok().unwrap();
+    let output = collector.finish().to_string();
+    assert!(output.contains("some error"));
+    assert!(output.contains("struct Foo"));
+}
+
+#[test]
+#[should_panic]
+fn test_shared_collector_nested_with() {
+    let shared = SharedCollector::new();
+    let other = shared.clone();
+    shared.with(|_| other.with(|_| ()));
+}
+
+#[test]
+fn test_shared_collector_debug() {
+    let shared = SharedCollector::new();
+    shared.error(Error::new(Span::call_site(), "some error"));
+    assert_eq!(
+        format!("{:?}", shared),
+        "SharedCollector { error_count: 1 }"
+    );
+
+    let _borrowed = shared.borrow_mut();
+    assert_eq!(format!("{:?}", shared), "SharedCollector { .. }");
+}
+
+#[test]
+fn test_map_errors() {
+    let mut collector = Collector::with_name("foo");
+    collector.map_errors(|e| Error::new(e.span(), format!("{} (see docs)", e)));
+    collector.error(Error::new(Span::call_site(), "some error"));
+
+    let messages: Vec<_> = collector.errors().map(ToString::to_string).collect();
+    assert_eq!(messages, ["some error"]);
+
+    let expected = Error::new(Span::call_site(), "foo: some error (see docs)");
+    assert_eq!(
+        collector.finish().to_string(),
+        expected.to_compile_error().to_string()
+    );
+}
+
+#[test]
+fn test_sections() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct Unnamed;
+    ));
+    collector.append_to("impls", quote!(impl A for B {}));
+    collector.append_to(
+        "other",
+        quote!(
+            struct Other;
+        ),
+    );
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand_into_section("types", &EchoExpand);
+    collector.append_to("impls", quote!(impl C for D {}));
+
+    assert_eq!(
+        collector.finish_ordered(&["types", "impls", "missing"]).to_string(),
+        "const FOO : bool = true ; impl A for B { } impl C for D { } struct Other ; struct Unnamed ;"
+    );
+}
+
+#[test]
+fn test_sections_first_use_order() {
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct Unnamed;
+    ));
+    collector.append_to(
+        "b",
+        quote!(
+            struct B;
+        ),
+    );
+    collector.append_to(
+        "a",
+        quote!(
+            struct A;
+        ),
+    );
+
+    assert_eq!(
+        collector.finish().to_string(),
+        "struct B ; struct A ; struct Unnamed ;"
+    );
+}
+
+#[test]
+fn test_debug() {
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct Foo;
+    ));
+    collector.error(Error::new(Span::call_site(), "something went wrong"));
+
+    let debug = format!("{:?}", collector);
+    assert!(debug.contains("error_count: 1"));
+    assert!(debug.contains("something went wrong"));
+    assert!(debug.contains("struct Foo ;"));
+
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+    let ctx = Context::new(&mut collector, data);
+    let debug = format!("{:?}", ctx);
+    assert!(debug.contains("something went wrong"));
+    assert!(debug.contains("has_data: true"));
+}
+
+#[test]
+fn test_debug_truncates_output() {
+    let mut collector = Collector::new();
+    for _ in 0..100 {
+        collector.append(quote!(
+            struct Foo;
+        ));
+    }
+
+    let debug = format!("{:?}", collector);
+    assert!(debug.contains("..."));
+    assert!(debug.len() < 500);
+}
+
+#[test]
+fn test_ok() {
+    let mut collector = Collector::new();
+    let ty: Option<syn::Type> = collector.ok(syn::parse2(quote!(u8)));
+    assert!(ty.is_some());
+    assert!(!collector.has_errors());
+
+    let ty: Option<syn::Type> = collector.ok(syn::parse2(quote!(;)));
+    assert!(ty.is_none());
+    assert_eq!(collector.error_count(), 1);
+}
+
+#[test]
+fn test_ok_or() {
+    let mut collector = Collector::new();
+    assert_eq!(
+        collector.ok_or(Some("foo"), Error::new(Span::call_site(), "missing")),
+        Some("foo")
+    );
+    assert!(!collector.has_errors());
+
+    assert_eq!(
+        collector.ok_or(None::<&str>, Error::new(Span::call_site(), "missing")),
+        None
+    );
+    assert_eq!(collector.errors().next().unwrap().to_string(), "missing");
+}
+
+#[test]
+fn test_output_budget() {
+    let data: syn::File = parse_quote!(
+        struct A;
+        struct B;
+        struct C;
+        struct D;
+    );
+
+    let mut collector = Collector::new();
+    collector.set_output_budget(4);
+    Context::new(&mut collector, data).expand(&EchoExpand);
+
+    let output = collector.finish().to_string();
+    assert_eq!(output.matches("output budget").count(), 1);
+    assert!(
+        output.contains("the expansion generated 12 token trees, exceeding the output budget of 4")
+    );
+}
+
+#[test]
+fn test_output_budget_not_exceeded() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    collector.set_output_budget(100);
+    Context::new(&mut collector, data).expand(&EchoExpand);
+
+    assert!(!collector.finish().to_string().contains("output budget"));
+}
+
+#[test]
+fn test_output_budget_counts_after_set() {
+    let mut collector = Collector::new();
+    collector.append(quote!(
+        struct A;
+        struct B;
+    ));
+    collector.set_output_budget(3);
+    collector.append(quote!(
+        struct C;
+    ));
+
+    assert!(!collector.finish().to_string().contains("output budget"));
+
+    let mut collector = Collector::new();
+    collector.set_output_budget(3);
+    collector.append(quote!(
+        struct A;
+        struct B;
+    ));
+
+    assert!(collector
+        .finish()
+        .to_string()
+        .contains("the expansion generated 6 token trees, exceeding the output budget of 3"));
+}
+
+struct MissingCrateAttrLint;
+
+impl Lint<DeriveInput> for MissingCrateAttrLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if let Data::Struct(s) = &input.data {
+            for field in s.fields.iter() {
+                c.error_once(
+                    "missing-crate-attr",
+                    Error::new_spanned(field, "missing `#[from_str(crate = ...)]`"),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_error_once() {
+    let data: DeriveInput = parse_quote!(
+        struct Foo {
+            a: u8,
+            b: u8,
+            c: u8,
+        }
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    assert_eq!(ctx.lint_counted(&MissingCrateAttrLint), Some(3));
+    assert!(collector.has_errors());
+    assert_eq!(collector.errors().len(), 1);
+
+    let output = collector.finish().to_string();
+    assert_eq!(output.matches("compile_error").count(), 1);
+}
+
+#[test]
+fn test_combined_error() {
+    let data: DeriveInput = syn::parse_str("enum Foo { A, B }").unwrap();
+
+    let mut collector = Collector::new();
+    assert!(collector.combined_error().is_none());
+
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&ErrorPerVariantLint);
+
+    let combined = collector.combined_error().unwrap();
+    let messages: Vec<_> = combined
+        .into_iter()
+        .map(|e| (e.to_string(), e.span().start().column))
+        .collect();
+    assert_eq!(
+        messages,
+        [("variant A".to_string(), 11), ("variant B".to_string(), 14)]
+    );
+    assert_eq!(collector.error_count(), 2);
+}
+
+struct ReverseErrorPerVariantLint;
+
+impl Lint<DeriveInput> for ReverseErrorPerVariantLint {
+    fn lint(&self, input: &DeriveInput, c: &mut Collector) {
+        if let Data::Enum(e) = &input.data {
+            for variant in e.variants.iter().rev() {
+                c.error_spanned(variant, format!("variant {}", variant.ident));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sort_diagnostics() {
+    let mut collector = Collector::new();
+    collector.set_sort_diagnostics(true);
+    let mut ctx = Context::<DeriveInput>::new_parse_str(&mut collector, "enum Foo { A, B, C }");
+    ctx.lint(&ReverseErrorPerVariantLint);
+    collector.error_at(Span::call_site(), "no position");
+
+    let (_, errors) = collector.into_parts();
+    let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        ["variant A", "variant B", "variant C", "no position"]
+    );
+}
+
+#[test]
+fn test_unsorted_diagnostics() {
+    let mut collector = Collector::new();
+    let mut ctx = Context::<DeriveInput>::new_parse_str(&mut collector, "enum Foo { A, B, C }");
+    ctx.lint(&ReverseErrorPerVariantLint);
+
+    let output = collector.finish().to_string();
+    let a = output.find("variant A").unwrap();
+    let c = output.find("variant C").unwrap();
+    assert!(c < a);
+}
+
+#[test]
+fn test_error_coded() {
+    let mut collector = Collector::with_name("#[derive(Foo)]");
+    collector.error_coded(
+        "MYMACRO001",
+        Error::new(Span::call_site(), "expected an enum"),
+    );
+    collector.error(Error::new(Span::call_site(), "uncoded"));
+
+    let entries: Vec<ErrorEntry> = collector.entries().collect();
+    assert_eq!(entries[0].code, Some("MYMACRO001"));
+    assert_eq!(entries[0].error.to_string(), "expected an enum");
+    assert_eq!(entries[1].code, None);
+
+    let output = collector.finish().to_string();
+    assert!(output.contains(r##""#[derive(Foo)]: [MYMACRO001] expected an enum""##));
+    assert!(output.contains(r##""#[derive(Foo)]: uncoded""##));
+}
+
+#[test]
+fn test_recover_rollback() {
+    let mut collector = Collector::new();
+    collector.error(Error::new(Span::call_site(), "first"));
+    assert!(collector.has_new_errors());
+
+    let checkpoint = collector.checkpoint();
+    collector.recover();
+    assert!(collector.has_errors());
+    assert!(!collector.has_new_errors());
+    collector.append(quote!(
+        struct Foo;
+    ));
+
+    collector.rollback(checkpoint);
+    assert!(collector.has_new_errors());
+    collector.append(quote!(
+        struct Bar;
+    ));
+
+    collector.take_errors();
+    assert!(!collector.has_new_errors());
+    collector.append(quote!(
+        struct Baz;
+    ));
+    assert_eq!(collector.finish().to_string(), "struct Baz ;");
+}
+
+#[cfg(feature = "full")]
+#[test]
+fn test_verify_output() {
+    struct BrokenExpand;
+
+    impl Expand<()> for BrokenExpand {
+        type Output = TokenStream;
+
+        fn expand(&self, _: &(), _: &mut Collector) -> Option<Self::Output> {
+            // an impl without a body
+            Some(quote!(impl Foo for Bar))
+        }
+    }
+
+    let mut collector = Collector::new();
+    collector.set_verify_output(true);
+    collector.append(quote!(
+        struct Foo;
+    ));
+    assert!(collector.finish_result().is_ok());
+
+    let mut collector = Collector::new();
+    collector.set_verify_output(true);
+    Context::new(&mut collector, ()).expand(&BrokenExpand);
+    let errors = collector.finish_result().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0]
+        .to_string()
+        .starts_with("the macro generated invalid code, this is a bug in the macro"));
+
+    // the output isn't verified if errors were reported
+    let mut collector = Collector::new();
+    collector.set_verify_output(true);
+    collector.append_unchecked(quote!(impl));
+    collector.error(Error::new(Span::call_site(), "some error"));
+    assert_eq!(collector.finish_result().unwrap_err().len(), 1);
+}
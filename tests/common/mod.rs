@@ -0,0 +1,13 @@
+use macro_compose::{Collector, Context, Lint};
+use syn::parse::Parse;
+
+/// parse and lint the input and return the messages and start columns of the errors
+pub fn lint<T: Parse>(lint: &impl Lint<T>, input: &str) -> Vec<(String, usize)> {
+    let input: T = syn::parse_str(input).unwrap();
+    let mut collector = Collector::new();
+    Context::new(&mut collector, input).lint(lint);
+    collector
+        .errors()
+        .map(|e| (e.to_string(), e.span().start().column))
+        .collect()
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "full")]
+
+mod common;
+
+use common::lint;
+use macro_compose::lints::{is_result, FnSignatureLint, ReceiverKind};
+use syn::ItemFn;
+
+#[test]
+fn test_fn_signature_lint_allows_all() {
+    let sig = FnSignatureLint::new();
+    assert!(lint::<ItemFn>(&sig, "async unsafe fn foo(&self) -> u8 { 42 }").is_empty());
+    assert!(lint::<ItemFn>(&sig, "fn foo() {}").is_empty());
+}
+
+#[test]
+fn test_fn_signature_lint_async_unsafe() {
+    let sig = FnSignatureLint::new().deny_async().deny_unsafe();
+    assert!(lint::<ItemFn>(&sig, "fn foo() {}").is_empty());
+    assert_eq!(
+        lint::<ItemFn>(&sig, "pub async fn foo() {}"),
+        [("async functions are not supported".to_string(), 4)]
+    );
+    assert_eq!(
+        lint::<ItemFn>(&sig, "async unsafe fn foo() {}"),
+        [
+            ("async functions are not supported".to_string(), 0),
+            ("unsafe functions are not supported".to_string(), 6)
+        ]
+    );
+}
+
+#[test]
+fn test_fn_signature_lint_receiver() {
+    let sig = FnSignatureLint::new().require_receiver(ReceiverKind::Ref);
+    assert!(lint::<ItemFn>(&sig, "fn foo(&self, a: u8) {}").is_empty());
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo(&mut self) {}"),
+        [("expected a `&self` parameter".to_string(), 7)]
+    );
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo(a: u8) {}"),
+        [("expected a `&self` parameter".to_string(), 6)]
+    );
+
+    let sig = FnSignatureLint::new().require_receiver(ReceiverKind::Value);
+    assert!(lint::<ItemFn>(&sig, "fn foo(mut self) {}").is_empty());
+
+    let sig = FnSignatureLint::new().deny_receiver();
+    assert!(lint::<ItemFn>(&sig, "fn foo(a: u8) {}").is_empty());
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo(self) {}"),
+        [("`self` parameters are not supported".to_string(), 7)]
+    );
+}
+
+#[test]
+fn test_fn_signature_lint_typed_receiver() {
+    let sig = FnSignatureLint::new().require_receiver(ReceiverKind::Ref);
+    assert!(lint::<ItemFn>(&sig, "fn foo(self: &Self) {}").is_empty());
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo(self: &mut Self) {}"),
+        [("expected a `&self` parameter".to_string(), 7)]
+    );
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo(self: Box<Self>) {}"),
+        [("expected a `&self` parameter".to_string(), 7)]
+    );
+
+    let sig = FnSignatureLint::new().require_receiver(ReceiverKind::RefMut);
+    assert!(lint::<ItemFn>(&sig, "fn foo(self: &mut Self) {}").is_empty());
+
+    let sig = FnSignatureLint::new().require_receiver(ReceiverKind::Value);
+    assert!(lint::<ItemFn>(&sig, "fn foo(mut self: Self) {}").is_empty());
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo(self: Box<Self>) {}"),
+        [("expected a `self` parameter".to_string(), 7)]
+    );
+
+    let sig = FnSignatureLint::new().deny_receiver();
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo(self: Box<Self>) {}"),
+        [("`self` parameters are not supported".to_string(), 7)]
+    );
+}
+
+#[test]
+fn test_fn_signature_lint_output() {
+    let sig = FnSignatureLint::new().require_output_matches(is_result);
+    assert!(lint::<ItemFn>(&sig, "fn foo() -> Result<(), ()> { Ok(()) }").is_empty());
+    assert!(lint::<ItemFn>(&sig, "fn foo() -> std::io::Result<()> { Ok(()) }").is_empty());
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo() -> u8 { 42 }"),
+        [("unexpected return type".to_string(), 12)]
+    );
+    assert_eq!(
+        lint::<ItemFn>(&sig, "fn foo() {}"),
+        [("expected a return type".to_string(), 6)]
+    );
+}
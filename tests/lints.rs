@@ -0,0 +1,498 @@
+mod common;
+
+use common::lint;
+use macro_compose::{
+    lints::{
+        AttributeAllowlistLint, BoundsLint, EnsureEnum, EnsureNamedFields, EnsureStruct,
+        EnsureTupleStruct, EnsureUnion, EnsureUnitVariants, FieldsShapeLint, GenericsLint,
+        MaxFieldsLint, MaxVariantsLint, MinVariantsLint, NoGenericsLint, RequireDocsLint,
+        ReservedIdentLint, UniqueNamesLint,
+    },
+    Collector, Context, LintExt,
+};
+use syn::DeriveInput;
+
+#[test]
+fn test_ensure_enum() {
+    assert!(lint(&EnsureEnum::new(), "enum Foo {}").is_empty());
+    assert_eq!(
+        lint(&EnsureEnum::new(), "pub struct Foo;"),
+        [("expected an enum".to_string(), 4)]
+    );
+    assert_eq!(
+        lint(&EnsureEnum::new(), "union Foo { a: u8 }"),
+        [("expected an enum".to_string(), 0)]
+    );
+}
+
+#[test]
+fn test_ensure_struct() {
+    assert!(lint(&EnsureStruct::new(), "struct Foo;").is_empty());
+    assert_eq!(
+        lint(&EnsureStruct::new(), "enum Foo {}"),
+        [("expected a struct".to_string(), 0)]
+    );
+}
+
+#[test]
+fn test_ensure_union() {
+    assert!(lint(&EnsureUnion::new(), "union Foo { a: u8 }").is_empty());
+    assert_eq!(
+        lint(&EnsureUnion::new(), "struct Foo;"),
+        [("expected a union".to_string(), 0)]
+    );
+}
+
+#[test]
+fn test_ensure_named_fields() {
+    assert!(lint(&EnsureNamedFields::new(), "struct Foo { a: u8 }").is_empty());
+    assert_eq!(
+        lint(&EnsureNamedFields::new(), "struct Foo(u8);"),
+        [("expected a struct with named fields".to_string(), 10)]
+    );
+    assert_eq!(
+        lint(&EnsureNamedFields::new(), "struct Foo;"),
+        [("expected a struct with named fields".to_string(), 7)]
+    );
+    assert_eq!(
+        lint(&EnsureNamedFields::new(), "enum Foo {}"),
+        [("expected a struct with named fields".to_string(), 0)]
+    );
+}
+
+#[test]
+fn test_ensure_tuple_struct() {
+    assert!(lint(&EnsureTupleStruct::new(), "struct Foo(u8);").is_empty());
+    assert_eq!(
+        lint(&EnsureTupleStruct::new(), "struct Foo { a: u8 }"),
+        [("expected a tuple struct".to_string(), 11)]
+    );
+}
+
+#[test]
+fn test_ensure_unit_variants() {
+    assert!(lint(&EnsureUnitVariants::new(), "enum Foo { A, B }").is_empty());
+    assert!(lint(&EnsureUnitVariants::new(), "struct Foo(u8);").is_empty());
+    assert_eq!(
+        lint(
+            &EnsureUnitVariants::new(),
+            "enum Foo { A, B(u8), C { c: u8 } }"
+        ),
+        [
+            ("expected a unit variant".to_string(), 15),
+            ("expected a unit variant".to_string(), 23)
+        ]
+    );
+}
+
+#[test]
+fn test_with_message() {
+    assert_eq!(
+        lint(
+            &EnsureEnum::new().with_message("#[derive(FromStr)] only supports enums"),
+            "struct Foo;"
+        ),
+        [("#[derive(FromStr)] only supports enums".to_string(), 0)]
+    );
+}
+
+#[test]
+fn test_no_generics_lint() {
+    assert!(lint::<DeriveInput>(&NoGenericsLint, "struct Foo;").is_empty());
+    assert_eq!(
+        lint::<DeriveInput>(&NoGenericsLint, "struct Foo<'a, T, const N: usize>;"),
+        [
+            ("generic parameters are not supported".to_string(), 11),
+            ("generic parameters are not supported".to_string(), 15),
+            ("generic parameters are not supported".to_string(), 18)
+        ]
+    );
+}
+
+#[test]
+fn test_generics_lint() {
+    let input = "struct Foo<'a, A, B, const N: usize>;";
+    assert!(lint::<DeriveInput>(&GenericsLint::new(), input).is_empty());
+    assert_eq!(
+        lint::<DeriveInput>(&GenericsLint::new().deny_lifetimes(), input),
+        [("lifetime parameters are not supported".to_string(), 11)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(&GenericsLint::new().deny_const_params(), input),
+        [("const parameters are not supported".to_string(), 21)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(&GenericsLint::new().max_type_params(1), input),
+        [("at most 1 type parameter is supported".to_string(), 18)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(
+            &GenericsLint::new().max_type_params(2),
+            "struct Foo<A, B, C>;"
+        ),
+        [("at most 2 type parameters are supported".to_string(), 17)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(&GenericsLint::new().deny_type_params(), input),
+        [
+            ("type parameters are not supported".to_string(), 15),
+            ("type parameters are not supported".to_string(), 18)
+        ]
+    );
+}
+
+#[test]
+fn test_generics_lint_focus() {
+    let focused = GenericsLint::new()
+        .deny_lifetimes()
+        .focus(|input: &DeriveInput| &input.generics);
+    assert_eq!(lint(&focused, "struct Foo<'a>(&'a u8);").len(), 1);
+}
+
+/// a lint, an input and the expected messages and columns
+type Case = (
+    FieldsShapeLint,
+    &'static str,
+    &'static [(&'static str, usize)],
+);
+
+#[test]
+fn test_fields_shape_lint() {
+    let cases: &[Case] = &[
+        (FieldsShapeLint::new(), "struct Foo { a: u8, b: u8 }", &[]),
+        (FieldsShapeLint::unit_only(), "enum Foo { A, B }", &[]),
+        (FieldsShapeLint::unit_only(), "struct Foo;", &[]),
+        (
+            FieldsShapeLint::unit_only(),
+            "enum Foo { A, B(u8), C { c: u8 } }",
+            &[("unexpected fields", 15), ("unexpected fields", 23)],
+        ),
+        (FieldsShapeLint::newtype_only(), "struct Foo(u8);", &[]),
+        (
+            FieldsShapeLint::newtype_only(),
+            "enum Foo { A(u8), B(u8, u8), C }",
+            &[
+                ("expected exactly one unnamed field", 19),
+                ("expected exactly one unnamed field", 29),
+            ],
+        ),
+        (
+            FieldsShapeLint::new().max_fields(1),
+            "struct Foo { a: u8, b: u8 }",
+            &[("expected at most 1 field", 11)],
+        ),
+        (
+            FieldsShapeLint::new().max_fields(2),
+            "enum Foo { A(u8, u8), B(u8, u8, u8) }",
+            &[("expected at most 2 fields", 23)],
+        ),
+        (
+            FieldsShapeLint::new().allow_named(false),
+            "enum Foo { A(u8), B { b: u8 } }",
+            &[("named fields are not supported", 20)],
+        ),
+        (
+            FieldsShapeLint::new().allow_named(false),
+            "union Foo { a: u8 }",
+            &[("named fields are not supported", 10)],
+        ),
+        (
+            FieldsShapeLint::unit_only().with_message("no fields allowed"),
+            "struct Foo(u8);",
+            &[("no fields allowed", 10)],
+        ),
+    ];
+
+    for (fields_lint, input, expected) in cases {
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(message, column)| (message.to_string(), *column))
+            .collect();
+        assert_eq!(lint(fields_lint, input), expected, "{}", input);
+    }
+}
+
+#[test]
+fn test_attribute_allowlist_lint() {
+    let allowlist = AttributeAllowlistLint::new("from_str", &["rename", "default", "skip"]);
+    let input = r#"
+#[from_str(default)]
+enum Foo {
+    #[from_str(rename = "bar", skip(always))]
+    #[other(unknown)]
+    Bar {
+        #[from_str(skip)]
+        bar: u8,
+    },
+}"#;
+    assert!(lint(&allowlist, input).is_empty());
+
+    let input = r#"
+#[from_str(defualt)]
+enum Foo {
+    #[from_str(renme = "bar")]
+    Bar {
+        #[from_str(unknown(always))]
+        bar: u8,
+    },
+}"#;
+    assert_eq!(
+        lint(&allowlist, input),
+        [
+            (
+                "unknown key `defualt` in `from_str` attribute, did you mean `default`?"
+                    .to_string(),
+                11
+            ),
+            (
+                "unknown key `renme` in `from_str` attribute, did you mean `rename`?".to_string(),
+                15
+            ),
+            (
+                "unknown key `unknown` in `from_str` attribute".to_string(),
+                19
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_attribute_allowlist_lint_malformed() {
+    let allowlist = AttributeAllowlistLint::new("from_str", &["rename"]);
+    let errors = lint(
+        &allowlist,
+        r#"
+#[from_str = "foo"]
+#[from_str("foo", foo::bar)]
+#[from_str = ]
+struct Foo;"#,
+    );
+    let messages: Vec<_> = errors.iter().map(|(message, _)| message.as_str()).collect();
+    assert_eq!(messages.len(), 4);
+    assert!(messages[0].starts_with("malformed `from_str` attribute"));
+    assert_eq!(messages[1], "expected `#[from_str(...)]`");
+    assert_eq!(messages[2], "expected a key in `from_str` attribute");
+    assert_eq!(messages[3], "expected a key in `from_str` attribute");
+}
+
+#[test]
+fn test_max_variants_lint() {
+    assert!(lint(&MaxVariantsLint(2), "enum Foo { A, B }").is_empty());
+    assert!(lint(&MaxVariantsLint(0), "struct Foo(u8, u8);").is_empty());
+    assert_eq!(
+        lint(&MaxVariantsLint(2), "enum Foo { A, B, C, D }"),
+        [("expected at most 2 variants, found 4".to_string(), 17)]
+    );
+    assert_eq!(
+        lint(&MaxVariantsLint(1), "enum Foo { A, B }"),
+        [("expected at most 1 variant, found 2".to_string(), 14)]
+    );
+}
+
+#[test]
+fn test_min_variants_lint() {
+    assert!(lint(&MinVariantsLint(1), "enum Foo { A }").is_empty());
+    assert_eq!(
+        lint(&MinVariantsLint(1), "enum Foo {}"),
+        [("expected at least 1 variant, found 0".to_string(), 9)]
+    );
+}
+
+#[test]
+fn test_max_fields_lint() {
+    assert!(lint(&MaxFieldsLint(2), "struct Foo(u8, u8);").is_empty());
+    assert_eq!(
+        lint(&MaxFieldsLint(2), "struct Foo(u8, u8, u8);"),
+        [("expected at most 2 fields, found 3".to_string(), 19)]
+    );
+    assert_eq!(
+        lint(&MaxFieldsLint(1), "enum Foo { A(u8), B { a: u8, b: u8 } }"),
+        [("expected at most 1 field, found 2".to_string(), 29)]
+    );
+}
+
+#[test]
+fn test_count_lints_compose() {
+    let counts = (MinVariantsLint(1), MaxVariantsLint(2));
+    assert!(lint(&counts, "enum Foo { A, B }").is_empty());
+    assert_eq!(lint(&counts, "enum Foo {}").len(), 1);
+
+    let counts = MaxVariantsLint(1).and(MaxFieldsLint(0));
+    assert_eq!(lint(&counts, "enum Foo { A(u8), B }").len(), 2);
+}
+
+#[test]
+fn test_reserved_ident_lint() {
+    let reserved = ReservedIdentLint::new().deny("Error").deny_prefix("Parse");
+    assert!(lint::<DeriveInput>(&reserved, "struct Foo;").is_empty());
+    assert!(lint::<DeriveInput>(&reserved, "struct r#Foo;").is_empty());
+    assert!(lint::<DeriveInput>(&reserved, "struct Foo__;").is_empty());
+    assert_eq!(
+        lint::<DeriveInput>(&reserved, "struct __Foo;"),
+        [("the name `__Foo` is reserved".to_string(), 7)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(&reserved, "struct r#type;"),
+        [("the name `type` is a keyword".to_string(), 7)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(&reserved, "enum Error {}"),
+        [("the name `Error` is reserved".to_string(), 5)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(&reserved, "struct ParseFoo;"),
+        [("the name `ParseFoo` is reserved".to_string(), 7)]
+    );
+}
+
+#[test]
+fn test_reserved_ident_lint_with_message() {
+    let reserved = ReservedIdentLint::new().with_message("this name is used by the generated code");
+    assert_eq!(
+        lint::<DeriveInput>(&reserved, "struct r#type;"),
+        [("this name is used by the generated code".to_string(), 7)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(&reserved, "struct __Foo;"),
+        [("this name is used by the generated code".to_string(), 7)]
+    );
+}
+
+#[test]
+fn test_bounds_lint() {
+    let input =
+        "struct Foo<'a, A: ?Sized + 'a, B: Clone, C>(&'a A, B, C) where C: std::clone::Clone;";
+    assert!(lint::<DeriveInput>(&BoundsLint::new(), input).is_empty());
+    assert_eq!(
+        lint::<DeriveInput>(&BoundsLint::new().deny_maybe_sized(), input),
+        [("`?Sized` bounds are not supported".to_string(), 18)]
+    );
+    assert_eq!(
+        lint::<DeriveInput>(&BoundsLint::new().deny_bound_on_param("Clone"), input),
+        [
+            ("`Clone` bounds are not supported".to_string(), 34),
+            ("`Clone` bounds are not supported".to_string(), 66)
+        ]
+    );
+}
+
+#[test]
+fn test_bounds_lint_where_clause() {
+    let deny_where = BoundsLint::new().deny_where_clauses();
+    assert!(lint::<DeriveInput>(&deny_where, "struct Foo<A: Clone>(A);").is_empty());
+    assert_eq!(
+        lint::<DeriveInput>(&deny_where, "struct Foo<A>(A) where A: Clone;"),
+        [("where clauses are not supported".to_string(), 17)]
+    );
+
+    let focused = BoundsLint::new()
+        .deny_maybe_sized()
+        .focus(|input: &DeriveInput| &input.generics);
+    assert_eq!(
+        lint(&focused, "struct Foo<A>(Box<A>) where A: ?Sized;"),
+        [("`?Sized` bounds are not supported".to_string(), 31)]
+    );
+}
+
+#[test]
+fn test_require_docs_lint() {
+    let docs = RequireDocsLint::new();
+    assert!(lint(&docs, "/// a foo\nstruct Foo { a: u8 }").is_empty());
+    assert_eq!(
+        lint(&docs, "struct Foo { a: u8 }"),
+        [("missing documentation for `Foo`".to_string(), 7)]
+    );
+    assert_eq!(
+        lint(&docs, "#[doc(hidden)] struct Foo;"),
+        [("missing documentation for `Foo`".to_string(), 22)]
+    );
+}
+
+#[test]
+fn test_require_docs_lint_variants_and_fields() {
+    let input =
+        r#"#[doc = "a foo"] enum Foo { #[doc = "a"] A(u8), B { #[doc = "b"] b: u8, c: u8 } }"#;
+    assert!(lint(&RequireDocsLint::new(), input).is_empty());
+    assert_eq!(
+        lint(&RequireDocsLint::new().variants(), input),
+        [("missing documentation for `B`".to_string(), 48)]
+    );
+    assert_eq!(
+        lint(&RequireDocsLint::new().fields(), input),
+        [
+            ("missing documentation for `0`".to_string(), 43),
+            ("missing documentation for `c`".to_string(), 72)
+        ]
+    );
+}
+
+#[test]
+fn test_require_docs_lint_deny_empty() {
+    let input = "///\n///   \nstruct Foo;";
+    assert!(lint(&RequireDocsLint::new(), input).is_empty());
+    assert_eq!(
+        lint(&RequireDocsLint::new().deny_empty(), input),
+        [("empty documentation for `Foo`".to_string(), 0)]
+    );
+    assert!(lint(
+        &RequireDocsLint::new().deny_empty(),
+        "///\n/// a foo\nstruct Foo;"
+    )
+    .is_empty());
+}
+
+fn lowercase_name(variant: &syn::Variant) -> String {
+    variant.ident.to_string().to_lowercase()
+}
+
+#[test]
+fn test_unique_names_lint() {
+    let unique = UniqueNamesLint::new(lowercase_name);
+    assert!(lint(&unique, "enum Foo { Foo, Bar, Baz }").is_empty());
+    assert!(lint(&unique, "struct Foo;").is_empty());
+    assert_eq!(
+        lint(&unique, "enum Foo { Foo, Bar, FOO, BAR, fOo }"),
+        [
+            ("the name `foo` is already used".to_string(), 21),
+            ("the name `bar` is already used".to_string(), 26),
+            ("the name `foo` is already used".to_string(), 31)
+        ]
+    );
+}
+
+#[test]
+fn test_unique_names_lint_note() {
+    let input: DeriveInput = syn::parse_str("enum Foo { Foo, FOO }").unwrap();
+    let mut collector = Collector::new();
+    Context::new(&mut collector, input).lint(&UniqueNamesLint::case_insensitive());
+
+    let errors = collector.finish_result().unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "the name `foo` is already used\nnote: `foo` is first used by `Foo`"
+    );
+}
+
+#[test]
+fn test_unique_names_lint_options() {
+    let rename = |variant: &syn::Variant| match variant.ident.to_string().as_str() {
+        "A" => " a".to_string(),
+        "B" => "a ".to_string(),
+        _ => "A".to_string(),
+    };
+    assert!(lint(&UniqueNamesLint::new(rename), "enum Foo { A, B, C }").is_empty());
+    assert_eq!(
+        lint(&UniqueNamesLint::new(rename).trim(), "enum Foo { A, B, C }"),
+        [("the name `a` is already used".to_string(), 14)]
+    );
+    assert_eq!(
+        lint(
+            &UniqueNamesLint::new(rename).trim().ignore_case(),
+            "enum Foo { A, B, C }"
+        ),
+        [
+            ("the name `a` is already used".to_string(), 14),
+            ("the name `a` is already used".to_string(), 17)
+        ]
+    );
+}
@@ -0,0 +1,65 @@
+use macro_compose::{report, try_report, Collector};
+use proc_macro2::Span;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use syn::Error;
+
+fn error(message: &str) -> Error {
+    Error::new(Span::call_site(), message)
+}
+
+fn messages(collector: &Collector) -> Vec<String> {
+    collector.errors().map(ToString::to_string).collect()
+}
+
+#[test]
+fn test_report() {
+    let mut collector = Collector::new();
+    let value = collector.enter_scope(|| {
+        report(error("first"));
+        report(error("second"));
+        42
+    });
+    assert_eq!(value, 42);
+    assert_eq!(messages(&collector), ["first", "second"]);
+}
+
+#[test]
+fn test_nested_scopes() {
+    let mut outer = Collector::new();
+    let mut inner = Collector::new();
+    outer.enter_scope(|| {
+        report(error("outer before"));
+        inner.enter_scope(|| report(error("inner")));
+        report(error("outer after"));
+    });
+    assert_eq!(messages(&outer), ["outer before", "outer after"]);
+    assert_eq!(messages(&inner), ["inner"]);
+}
+
+#[test]
+fn test_panic_in_scope() {
+    let mut collector = Collector::new();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        collector.enter_scope(|| {
+            report(error("before panic"));
+            panic!("oops");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(messages(&collector), ["before panic"]);
+
+    // the scope was left, so there's no active collector anymore
+    assert!(try_report(error("no scope")).is_err());
+}
+
+#[test]
+fn test_no_scope() {
+    let e = try_report(error("no scope")).unwrap_err();
+    assert_eq!(e.to_string(), "no scope");
+}
+
+#[test]
+#[should_panic(expected = "outside of `Collector::enter_scope`")]
+fn test_report_without_scope_panics() {
+    report(error("no scope"));
+}
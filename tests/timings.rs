@@ -0,0 +1,66 @@
+#![cfg(feature = "timings")]
+
+use macro_compose::{Collector, Context, EchoExpand, ExpandPass, Lint, LintPass, Pass};
+use syn::{parse_quote, ItemConst};
+
+struct NoOpLint;
+
+impl Lint<ItemConst> for NoOpLint {
+    fn lint(&self, _: &ItemConst, _: &mut Collector) {}
+}
+
+#[test]
+fn test_timings() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.lint(&NoOpLint);
+    ctx.expand(&EchoExpand);
+    ctx.capture(&EchoExpand);
+
+    let names: Vec<_> = collector
+        .timings()
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "timings::NoOpLint",
+            "macro_compose::EchoExpand",
+            "macro_compose::EchoExpand"
+        ]
+    );
+}
+
+#[test]
+fn test_timings_erased() {
+    let data: ItemConst = parse_quote!(
+        const FOO: bool = true;
+    );
+
+    let mut collector = Collector::new();
+    let mut ctx = Context::new(&mut collector, data);
+    ctx.expand_all(&[&EchoExpand]);
+    ctx.capture_all(&[&EchoExpand]);
+    ctx.run(&LintPass(NoOpLint));
+    ctx.run_all(&[&ExpandPass(EchoExpand) as &dyn Pass<ItemConst>]);
+
+    let names: Vec<_> = collector
+        .timings()
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "macro_compose::EchoExpand",
+            "macro_compose::EchoExpand",
+            "macro_compose::LintPass<timings::NoOpLint>",
+            "macro_compose::ExpandPass<macro_compose::EchoExpand>"
+        ]
+    );
+}